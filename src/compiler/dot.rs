@@ -0,0 +1,259 @@
+/*
+a program as graphviz, rpl dump --format dot source.rpl | dot -Tsvg
+
+the scopes are clusters inside the clusters of the graphs that hold
+them, a point is a node labelled with its line and its value once
+folded. a scope whose parent isn't the graph around it, like the root
+under the prelude, has a <parent> edge to it
+
+a clock is a circle, a signal a bold edge labelled ? from the points
+that fire it to the point it gates, count: 1s ? count + 1
+
+the reduced points are a second graph, an edge from each point to the
+points that need it, clustered by the graph that names them. a dotted
+edge goes from a labelled point to the point it was reduced to. a latch
+has a bold ? edge from its signal and a dashed one to the register it
+writes
+*/
+
+use super::expander::{Kind, Maps};
+use super::ir::Ir;
+use super::parser::{Ast, EID};
+use super::reducer::{operands, Point};
+use super::tokenizer::Name;
+use std::collections::BTreeMap;
+
+pub fn dot(
+	maps: &Maps,
+	spans: &BTreeMap<String, u32>,
+	ir: Result<&Ir, String>,
+) -> String {
+	let mut output = String::from(
+		"digraph rpl {\n\tcompound=true;\n\tnode [shape=box, fontname=monospace];\n\n",
+	);
+
+	// every scope under the graph its path is in
+	let mut children: BTreeMap<EID, Vec<EID>> = BTreeMap::new();
+	for (eid, scope) in maps.scopes.iter().enumerate().skip(1) {
+		children.entry(enclosing(maps, eid)).or_default().push(eid);
+		if scope.parent != 0 && scope.parent != enclosing(maps, eid) {
+			output.push_str(&format!(
+				"\ts{} -> s{} [label=\"<parent>\", style=dashed, ltail=cluster_s{}, lhead=cluster_s{}];\n",
+				eid, scope.parent, eid, scope.parent
+			));
+		}
+	}
+	let reduced = ir.as_ref().ok().copied();
+	for eid in children.get(&0).into_iter().flatten() {
+		scope(&mut output, maps, spans, reduced, &children, *eid, 1);
+	}
+	for (path, symbol) in &maps.table {
+		if let Ast::Op2(Name::Signal, signal, _) = &symbol.point {
+			let mut signals = Vec::new();
+			maps.deps(signal, false, &mut signals);
+			for signal in signals {
+				output.push_str(&format!(
+					"\t\"{}\" -> \"{}\" [label=\"?\", style=bold];\n",
+					quote(&signal),
+					quote(path)
+				));
+			}
+		}
+	}
+
+	let ir = match ir {
+		Ok(ir) => ir,
+		Err(e) => {
+			output.push_str(&format!(
+				"\n\tlabel=\"the points aren't drawn, {}\";\n}}\n",
+				quote(&e)
+			));
+			return output;
+		}
+	};
+
+	// the reduced points, by the graph that names them
+	let mut named: BTreeMap<usize, &str> = BTreeMap::new();
+	for (path, idx) in &ir.labels {
+		named.entry(*idx).or_insert(path);
+	}
+	let mut clusters: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+	for idx in 0..ir.code.len() {
+		let graph = match named.get(&idx) {
+			Some(path) => {
+				path.rsplit_once('.').map_or("", |(graph, _)| graph)
+			}
+			None => "",
+		};
+		clusters.entry(graph).or_default().push(idx);
+	}
+	output.push_str(
+		"\n\tsubgraph cluster_points {\n\t\tlabel=\"points\";\n",
+	);
+	for (n, (graph, idxs)) in clusters.iter().enumerate() {
+		let tabs = match graph.is_empty() {
+			true => "\t\t",
+			false => {
+				output.push_str(&format!(
+					"\t\tsubgraph cluster_p{} {{\n\t\t\tlabel=\"{}\";\n",
+					n,
+					quote(graph)
+				));
+				"\t\t\t"
+			}
+		};
+		for idx in idxs {
+			output.push_str(&format!(
+				"{}p{} [label=\"%{} {}\"{}];\n",
+				tabs,
+				idx,
+				idx,
+				quote(&point(ir, *idx)),
+				match ir.code[*idx] {
+					Point::Input(_) => ", shape=invhouse",
+					Point::Clock(_) => ", shape=circle",
+					Point::Register => ", shape=box3d",
+					Point::Integer(_) | Point::Decimal(_) =>
+						", shape=plain",
+					_ => "",
+				}
+			));
+		}
+		if !graph.is_empty() {
+			output.push_str("\t\t}\n");
+		}
+	}
+	output.push_str("\t}\n\n");
+	for (idx, point) in ir.code.iter().enumerate() {
+		if let Point::Latch(r, s, v) = point {
+			output.push_str(&format!(
+				"\tp{} -> p{} [label=\"?\", style=bold];\n\tp{} -> p{};\n\tp{} -> p{} [style=dashed, constraint=false];\n",
+				s, idx, v, idx, idx, r
+			));
+			continue;
+		}
+		for operand in operands(point) {
+			output.push_str(&format!("\tp{} -> p{};\n", operand, idx));
+		}
+	}
+	for (path, idx) in &ir.labels {
+		if maps
+			.at(path)
+			.is_some_and(|symbol| symbol.kind != Kind::Ordinal)
+		{
+			output.push_str(&format!(
+				"\t\"{}\" -> p{} [style=dotted, arrowhead=none, constraint=false];\n",
+				quote(path),
+				idx
+			));
+		}
+	}
+	output.push_str("}\n");
+	output
+}
+
+// the scope whose path holds this one, 0 for a scope at the top
+fn enclosing(maps: &Maps, eid: EID) -> EID {
+	match maps.scopes[eid].path.rsplit_once('.') {
+		Some((graph, _)) => maps.paths.get(graph).cloned().unwrap_or(0),
+		None => 0,
+	}
+}
+
+fn scope(
+	output: &mut String,
+	maps: &Maps,
+	spans: &BTreeMap<String, u32>,
+	ir: Option<&Ir>,
+	children: &BTreeMap<EID, Vec<EID>>,
+	eid: EID,
+	depth: usize,
+) {
+	let tabs = "\t".repeat(depth);
+	let path = &maps.scopes[eid].path;
+	output.push_str(&format!(
+		"{}subgraph cluster_s{} {{\n{}\tlabel=\"{}\";\n{}\ts{} [shape=point, style=invis];\n",
+		tabs,
+		eid,
+		tabs,
+		quote(&spanned(spans, path)),
+		tabs,
+		eid
+	));
+	for (label, symbol) in maps.points(eid) {
+		if symbol.kind == Kind::Ordinal {
+			continue;
+		}
+		let path = maps.path(eid, label);
+		let mut text = spanned(spans, &path);
+		text =
+			text.replacen(&format!("{}.", maps.scopes[eid].path), "", 1);
+		if let Ast::Clock(period, _) = &symbol.point {
+			text.push_str(&format!("\nclock {}", period));
+		}
+		if let Some((ir, idx)) =
+			ir.and_then(|ir| Some((ir, ir.labels.get(&path)?)))
+		{
+			match ir.code[*idx] {
+				Point::Integer(v) => text.push_str(&format!("\n= {}", v)),
+				Point::Decimal(v) => {
+					text.push_str(&format!("\n= {:?}", v))
+				}
+				_ => {}
+			}
+		}
+		output.push_str(&format!(
+			"{}\t\"{}\" [label=\"{}\"{}];\n",
+			tabs,
+			quote(&path),
+			quote(&text),
+			match (&symbol.point, symbol.kind) {
+				(Ast::Clock(_, _), _) => ", shape=circle",
+				(_, Kind::Alias) => ", style=dashed",
+				(_, Kind::Range) => ", shape=diamond",
+				_ => "",
+			}
+		));
+	}
+	for child in children.get(&eid).into_iter().flatten() {
+		scope(output, maps, spans, ir, children, *child, depth + 1);
+	}
+	output.push_str(&format!("{}}}\n", tabs));
+}
+
+// root.a (line 3)
+fn spanned(spans: &BTreeMap<String, u32>, path: &str) -> String {
+	match spans.get(path) {
+		Some(line) => format!("{} (line {})", path, line),
+		None => path.to_string(),
+	}
+}
+
+// what a reduced point does, or its value when it's a constant
+fn point(ir: &Ir, idx: usize) -> String {
+	match &ir.code[idx] {
+		Point::Integer(v) => format!("= {}", v),
+		Point::Decimal(v) => format!("= {:?}", v),
+		Point::Input(size) => format!("input {:?}", size),
+		Point::Op1(name, _) | Point::Op2(name, _, _) => {
+			format!("{:?}", name)
+		}
+		Point::Graph(points) => format!("graph of {}", points.len()),
+		Point::If(_, _, _) => "if".to_string(),
+		Point::Index(_, _) => "index".to_string(),
+		Point::Filter(_, _) => "filter".to_string(),
+		Point::Tally(_) => "tally".to_string(),
+		Point::Clock(period) => format!("clock {}", period),
+		Point::Register => "register".to_string(),
+		Point::Latch(_, _, _) => "latch".to_string(),
+		Point::Sequence(n) => format!("{}, {}, ..", n, n + 1),
+		Point::Error(e) => format!("error {}", e),
+		Point::Dummy => "dummy".to_string(),
+	}
+}
+
+fn quote(text: &str) -> String {
+	text.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
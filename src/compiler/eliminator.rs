@@ -0,0 +1,154 @@
+/*
+removes the points nothing observed needs
+
+a program keeps its inputs and clocks, they're written and fired from
+outside, the labelled points of its root, which are what's observed,
+the return of every graph and any point pinned with --keep. every point
+one of those needs is kept too, the rest were folded into constants or
+are never asked for. a register that's kept keeps what's latched into it
+*/
+
+use super::expander::is_ordinal;
+use super::parser::IDX;
+use super::reducer::{constant, operands, Point, Program};
+use std::collections::BTreeMap;
+
+// why a point is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Root {
+	Input,
+	Clock,
+	Observed,
+	Return,
+	Kept,
+}
+
+pub fn eliminator(
+	program: &mut Program,
+	keep: &[String],
+) -> Result<String, String> {
+	let mut roots: BTreeMap<IDX, Root> = BTreeMap::new();
+	for (idx, point) in program.code.iter().enumerate() {
+		match point {
+			Point::Input(_) => {
+				roots.insert(idx, Root::Input);
+			}
+			Point::Clock(_) => {
+				roots.insert(idx, Root::Clock);
+			}
+			_ => {}
+		}
+	}
+	// the value of the program is observed, so are its labelled points
+	roots.entry(program.root).or_insert(Root::Observed);
+	for ((eid, label), idx) in &program.points {
+		if label == "<return>" {
+			roots.entry(*idx).or_insert(Root::Return);
+		} else if *eid == 1 && !is_ordinal(label) {
+			roots.entry(*idx).or_insert(Root::Observed);
+		}
+	}
+	for path in keep {
+		let idx = program
+			.points
+			.iter()
+			.find(|((eid, label), _)| {
+				program.envs.path(*eid, label) == *path
+			})
+			.map(|(_, idx)| *idx);
+		match idx {
+			Some(idx) => {
+				roots.insert(idx, Root::Kept);
+			}
+			None if program.envs.at(path).is_some() => {
+				return Err(format!(
+					"--keep {}, nothing refers to it, so it's never reduced",
+					path
+				))
+			}
+			None => {
+				return Err(format!(
+					"--keep {} is not a point, points are kept by path, like root.a.b",
+					path
+				))
+			}
+		}
+	}
+
+	// everything a root needs, a loop needs the graphs it reads too
+	let mut live = vec![false; program.code.len()];
+	let mut stack: Vec<IDX> = roots.keys().cloned().collect();
+	while !stack.is_empty() {
+		while let Some(idx) = stack.pop() {
+			if live[idx] {
+				continue;
+			}
+			live[idx] = true;
+			stack.extend(operands(&program.code[idx]));
+			if let Some((_, a, b)) = program.each.get(&idx) {
+				stack.extend([*a, *b]);
+			}
+		}
+		// the latches of the registers kept, and what they need
+		for (idx, point) in program.code.iter().enumerate() {
+			if let Point::Latch(r, _, _) = point {
+				if live[*r] && !live[idx] {
+					stack.push(idx);
+				}
+			}
+		}
+	}
+
+	let count =
+		|root: Root| roots.values().filter(|r| **r == root).count();
+	let mut report = format!(
+		"kept {} of {} points, roots: {} inputs, {} clocks, {} observed, {} returns, {} kept\n",
+		live.iter().filter(|live| **live).count(),
+		live.len(),
+		count(Root::Input),
+		count(Root::Clock),
+		count(Root::Observed),
+		count(Root::Return),
+		count(Root::Kept)
+	);
+
+	let mut labels: BTreeMap<IDX, Vec<String>> = BTreeMap::new();
+	for ((eid, label), idx) in &program.points {
+		if !is_ordinal(label) {
+			labels
+				.entry(*idx)
+				.or_default()
+				.push(program.envs.path(*eid, label));
+		}
+	}
+	let (mut folded, mut unused) = (0, 0);
+	for (idx, point) in program.code.iter().enumerate() {
+		if live[idx] {
+			continue;
+		}
+		match labels.get(&idx) {
+			Some(paths) => report.push_str(&format!(
+				"\nremoved {}: {:?}, nothing kept needs it",
+				paths.join(", "),
+				point
+			)),
+			None if constant(point) => folded += 1,
+			None => unused += 1,
+		}
+	}
+	if folded > 0 {
+		report.push_str(&format!(
+			"\nremoved {} unnamed constants, they're folded into the points that need them",
+			folded
+		));
+	}
+	if unused > 0 {
+		report.push_str(&format!(
+			"\nremoved {} unnamed points, nothing kept needs them",
+			unused
+		));
+	}
+
+	program.renumber(&live);
+	Ok(report)
+}
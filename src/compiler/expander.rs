@@ -1,42 +1,300 @@
-// create Btree of ast nodes
 // create Btree of typ nodes
 
-/**
- * add unnmaed points to btree, use their index as their key
- * for example: 6 -> "6"
+/*
+ * every point is in one btree under its path, the labels of the graphs
+ * that hold it, root.q.a.b.d. a point is also under its index, 6 ->
+ * root.q.6, and the index of a named point is a ref to its label
  *
+ * a graph without a label is named by its eid, root.q.<7>
  */
 // #![allow(dead_code)]
 // #![allow(unused_variables)]
-use super::parser::{Ast, EID};
-use super::tokenizer::Name;
-use std::collections::BTreeMap;
+use super::parser::{self, Ast, EID};
+use super::tokenizer::{self, Name};
+use std::collections::{BTreeMap, BTreeSet};
 
-pub type Maps = Vec<BTreeMap<String, Ast>>;
-pub type Typs = Vec<BTreeMap<String, Typ>>;
+// graphs written in rpl that every program can refer to
+const PRELUDE: &str = include_str!("prelude.rpl");
 
-pub enum Typ {}
+// every point of every graph, by path
+#[derive(Debug, Clone)]
+pub struct Maps {
+	pub table: BTreeMap<String, Symbol>,
+	pub scopes: Vec<Scope>, // by eid, 0 is no scope
+	pub paths: BTreeMap<String, EID>, // the eid of every scope
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+	pub path: String,
+	pub parent: EID,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+	pub point: Ast,
+	pub parent: EID, // the scope that defines it
+	pub kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+	Point,   // label: point
+	Ordinal, // the position of a point, named or not
+	Alias,   // a point of another graph, by a spread, selection or pattern
+	Range,   // the index a labelled range binds, 10 < i < 20
+}
+
+impl Maps {
+	fn new() -> Self {
+		Maps {
+			table: BTreeMap::new(),
+			scopes: vec![Scope {
+				path: String::new(),
+				parent: 0,
+			}],
+			paths: BTreeMap::new(),
+		}
+	}
+
+	// how many scopes there are, eids are below it
+	pub fn len(&self) -> usize {
+		self.scopes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.scopes.len() <= 1
+	}
+
+	pub fn parent(&self, eid: EID) -> EID {
+		self.scopes[eid].parent
+	}
+
+	pub fn path(&self, eid: EID, label: &str) -> String {
+		format!("{}.{}", self.scopes[eid].path, label)
+	}
+
+	pub fn get(&self, eid: EID, label: &str) -> Option<&Ast> {
+		self.table.get(&self.path(eid, label)).map(|s| &s.point)
+	}
+
+	// any point, by its path
+	pub fn at(&self, path: &str) -> Option<&Symbol> {
+		self.table.get(path)
+	}
+
+	fn insert(&mut self, eid: EID, label: &str, point: Ast, kind: Kind) {
+		let symbol = Symbol {
+			point,
+			parent: eid,
+			kind,
+		};
+		self.table.insert(self.path(eid, label), symbol);
+	}
+
+	// a new scope, under its label when it has one that isn't taken
+	fn scope(&mut self, parent: EID, label: Option<String>) -> EID {
+		let eid = self.scopes.len();
+		let base = match parent {
+			0 => String::new(),
+			_ => format!("{}.", self.scopes[parent].path),
+		};
+		let path = match label {
+			Some(label)
+				if !self.paths.contains_key(&(base.clone() + &label)) =>
+			{
+				base + &label
+			}
+			_ => format!("{}<{}>", base, eid),
+		};
+		self.paths.insert(path.clone(), eid);
+		self.scopes.push(Scope { path, parent });
+		eid
+	}
+
+	// the points a scope defines, under their labels
+	pub fn points(
+		&self,
+		eid: EID,
+	) -> impl Iterator<Item = (&str, &Symbol)> + '_ {
+		let prefix = format!("{}.", self.scopes[eid].path);
+		let len = prefix.len();
+		self.table
+			.range(prefix.clone()..)
+			.take_while(move |(path, _)| path.starts_with(&prefix))
+			.map(move |(path, symbol)| (&path[len..], symbol))
+			.filter(|(label, _)| !label.contains('.'))
+	}
+
+	// the paths of the points a point needs the value of, the value of
+	// a register too when registers is set
+	pub fn deps(
+		&self,
+		ast: &Ast,
+		registers: bool,
+		deps: &mut Vec<String>,
+	) {
+		match ast {
+			Ast::Ref(eid, label) => {
+				if let Ok((eid, _)) = find(self, label, *eid) {
+					deps.push(self.path(eid, label));
+				}
+			}
+			Ast::Graph(eid, points) => {
+				for point in points {
+					match point {
+						Ast::Key(label, _) => {
+							deps.push(self.path(*eid, label))
+						}
+						_ => self.deps(point, registers, deps),
+					}
+				}
+			}
+			// the value of a signalled point is a register, it's read
+			// before it's written
+			Ast::Op2(Name::Signal, signal, _) if !registers => {
+				self.deps(signal, registers, deps)
+			}
+			Ast::Op2(_, a, b) | Ast::Apply(a, b) => {
+				self.deps(a, registers, deps);
+				self.deps(b, registers, deps);
+			}
+			Ast::Op1(_, a) | Ast::Key(_, a) => {
+				self.deps(a, registers, deps)
+			}
+			Ast::If(a, b, c) => {
+				self.deps(a, registers, deps);
+				self.deps(b, registers, deps);
+				self.deps(c, registers, deps);
+			}
+			Ast::Range(_, lo, _, hi) => {
+				self.deps(lo, registers, deps);
+				self.deps(hi, registers, deps);
+			}
+			Ast::Space(sizes) => {
+				for size in sizes {
+					self.deps(size, registers, deps);
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+// how an applied graph is expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+	Full,      // every point of the source is copied into the instance
+	Selective, // a variable source shares the points its overrides don't reach
+}
 
+// the points an instance refers to in its source instead of copying them
 #[derive(Debug, Clone)]
-pub struct Parse {
+pub struct Share {
+	pub instance: String,
+	pub source: String,
+	pub labels: Vec<String>,
+	pub points: usize, // of the source
+	pub nodes: usize,  // a copy of the shared points would have
+}
+
+// how many nodes each strategy expands a program to
+#[derive(Debug, Clone)]
+pub struct Sizes {
+	pub strategy: Strategy,
+	pub nodes: usize, // expanded, prelude included
+	pub shares: Vec<Share>,
+}
+
+impl Sizes {
+	pub fn full(&self) -> usize {
+		match self.strategy {
+			Strategy::Full => self.nodes,
+			Strategy::Selective => self.nodes + self.saved(),
+		}
+	}
+
+	pub fn selective(&self) -> usize {
+		match self.strategy {
+			Strategy::Full => self.nodes - self.saved(),
+			Strategy::Selective => self.nodes,
+		}
+	}
+
+	fn saved(&self) -> usize {
+		self.shares.iter().map(|share| share.nodes).sum()
+	}
+
+	pub fn report(&self) -> String {
+		let mut report = format!(
+			"strategy: {:?}\nfull: {} nodes\nselective: {} nodes\n",
+			self.strategy,
+			self.full(),
+			self.selective()
+		);
+		for share in &self.shares {
+			report.push_str(&format!(
+				"\n{} shares {} of {} points of {}, {} nodes: {}",
+				share.instance,
+				share.labels.len(),
+				share.points,
+				share.source,
+				share.nodes,
+				share.labels.join(", ")
+			));
+		}
+		report
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Parse<'a> {
 	pub envs: Maps,
+	label: Option<String>, // the label of the graph being mapped
+	defining: Vec<(EID, String)>, // the labelled points being mapped
+	outside: BTreeSet<(EID, String)>, // points applied in parens, F(x)
+	spans: &'a BTreeMap<String, u32>, // the line of a point, by path
+	strategy: Strategy,
+	shared: BTreeMap<String, Ast>, // the source point of a shared point, by path
+	sizes: Vec<Share>,
+	nodes: usize, // of the prelude
 }
 
-pub fn expander(ast: &Ast) -> Result<(Ast, Maps), String> {
+pub fn expander(
+	ast: &Ast,
+	spans: &BTreeMap<String, u32>,
+	strategy: Strategy,
+) -> Result<(Ast, Maps, Sizes), String> {
 	let mut parse = Parse {
-		envs: vec![BTreeMap::new()], // 0th un-used, parent_eid 0 used to mean "no-parent"
+		envs: Maps::new(),
+		label: None,
+		defining: Vec::new(),
+		outside: BTreeSet::new(),
+		spans,
+		strategy,
+		shared: BTreeMap::new(),
+		sizes: Vec::new(),
+		nodes: 0,
 	};
 
-	let a = parse.map(ast, 0)?;
-	dbg!(&parse.envs);
-	Ok((a, parse.envs))
-}
+	// the program root is always eid 1, its parent is the prelude
+	let root = parse.envs.scope(0, Some("root".to_string()));
+	let prelude = parse.prelude()?;
+	parse.envs.scopes[root].parent = prelude;
 
-fn get_eid(ast: &Ast) -> Result<EID, String> {
-	match ast {
-		Ast::Graph(eid, _) => Ok(*eid),
-		_ => Err(format!("{:?} is not a Graph, thus no eid", &ast)),
-	}
+	let a = match ast {
+		Ast::Graph(_, points) => {
+			Ast::Graph(root, parse.map_points(points, root)?)
+		}
+		_ => parse.map(ast, root)?,
+	};
+	parse.cycles()?;
+	let sizes = Sizes {
+		strategy,
+		nodes: size(&a) + parse.nodes,
+		shares: parse.sizes,
+	};
+	Ok((a, parse.envs, sizes))
 }
 
 fn get_label(ast: &Ast) -> Result<String, String> {
@@ -46,6 +304,65 @@ fn get_label(ast: &Ast) -> Result<String, String> {
 	}
 }
 
+// the labels and positions of a graph an apply replaces or adds
+fn changed(s_points: &[Ast], m_points: &[Ast]) -> BTreeSet<String> {
+	let mut changed = BTreeSet::new();
+	for m_point in m_points {
+		match m_point {
+			Ast::Op1(Name::Add, point) => {
+				if let Ast::Key(label, _) = &**point {
+					changed.insert(label.clone());
+				}
+			}
+			Ast::Key(label, _) if is_ordinal(label) => {
+				let position =
+					label.parse::<usize>().unwrap_or(usize::MAX);
+				if let Some(Ast::Key(label, _)) = s_points.get(position) {
+					changed.insert(label.clone());
+				}
+				changed.insert(label.clone());
+			}
+			Ast::Key(label, _) => {
+				changed.insert(label.clone());
+			}
+			_ => {}
+		}
+	}
+	changed
+}
+
+// an input, a clock or a register, a value only known once running
+fn source(ast: &Ast) -> bool {
+	match ast {
+		Ast::Space(_) | Ast::Clock(_, _) => true,
+		Ast::Op2(Name::Signal, _, _) => true,
+		// [4 i32]! {..} is typed by its space, it isn't an input
+		Ast::Op2(Name::Sizer, left, right) => match **left {
+			Ast::Space(_) => source(right),
+			_ => source(left),
+		},
+		Ast::Op2(_, a, b) | Ast::Apply(a, b) => source(a) || source(b),
+		Ast::Op1(_, a) | Ast::Key(_, a) => source(a),
+		Ast::If(a, b, c) => source(a) || source(b) || source(c),
+		Ast::Range(_, lo, _, hi) => source(lo) || source(hi),
+		Ast::Graph(_, points) => points.iter().any(source),
+		_ => false,
+	}
+}
+
+// how many nodes an ast has
+pub fn size(ast: &Ast) -> usize {
+	1 + match ast {
+		Ast::Op2(_, a, b) | Ast::Apply(a, b) => size(a) + size(b),
+		Ast::Op1(_, a) | Ast::Key(_, a) => size(a),
+		Ast::If(a, b, c) => size(a) + size(b) + size(c),
+		Ast::Range(_, lo, _, hi) => size(lo) + size(hi),
+		Ast::Graph(_, points) => points.iter().map(size).sum(),
+		Ast::Space(sizes) => sizes.iter().map(size).sum(),
+		_ => 0,
+	}
+}
+
 fn key_match(point_s: &Ast, point_m: &Ast) -> bool {
 	match point_s {
 		Ast::Key(label_s, _) => {
@@ -61,114 +378,186 @@ fn key_match(point_s: &Ast, point_m: &Ast) -> bool {
 	}
 }
 
-fn has_key(points: &Vec<Ast>, point_s: &Ast) -> bool {
-	for point_m in points {
-		if key_match(point_s, point_m) {
-			return true;
+impl Parse<'_> {
+	fn prelude(&mut self) -> Result<EID, String> {
+		let tokens = tokenizer::tokenizer(PRELUDE)
+			.map_err(|e| format!("prelude: {}", e))?;
+		let ast = parser::parser(&tokens)
+			.map_err(|e| format!("prelude: {}", e))?;
+		self.label = Some("prelude".to_string());
+		match self.map(&ast, 0) {
+			Ok(graph @ Ast::Graph(eid, _)) => {
+				self.nodes = size(&graph);
+				Ok(eid)
+			}
+			Ok(x) => Err(format!("prelude: {:?} is not a Graph", x)),
+			Err(e) => Err(format!("prelude: {}", e)),
 		}
 	}
-	false
-}
 
-fn get_key(points: &Vec<Ast>, point_s: &Ast) -> Option<Ast> {
-	for point_m in points {
-		if key_match(point_s, point_m) {
-			return Some(point_m.clone());
-		}
+	fn scope(&mut self, parent_eid: EID) -> EID {
+		let label = self.label.take();
+		self.envs.scope(parent_eid, label)
 	}
-	None
-}
 
-impl Parse {
-	fn map(&mut self, ast: &Ast, parent_eid: EID) -> Result<Ast, String> {
-		match ast {
-			Ast::Graph(_, points) => {
-				let index = self.envs.len();
-				let mut env = BTreeMap::new();
-				env.insert("<parent>".to_string(), Ast::Parent(parent_eid));
-
-				self.envs.push(env);
-
-				let mut expanded_points = Vec::new();
-				for point in points {
-					expanded_points.push(self.map(point, index)?);
+	fn map_points(
+		&mut self,
+		points: &[Ast],
+		eid: EID,
+	) -> Result<Vec<Ast>, String> {
+		// declare every label before mapping, so points can refer to
+		// points defined after them, or to themselves through a signal.
+		// a label a pattern binds is bound once in its graph
+		let mut declared: BTreeMap<String, bool> = BTreeMap::new();
+		for point in points {
+			let pattern = matches!(point, Ast::Op2(Name::Pattern, _, _));
+			for label in declared_labels(point) {
+				match declared.insert(label.clone(), pattern) {
+					Some(other) if pattern || other => {
+						return Err(format!(
+							"{} is bound twice in {}, a pattern can't bind a label that is already a point, rename it like Point {{x: x'2}} :: p",
+							label, self.envs.scopes[eid].path
+						))
+					}
+					_ => {}
 				}
-
-				Ok(Ast::Graph(index, expanded_points))
+				self.envs.insert(eid, &label, Ast::Nothing, Kind::Point);
 			}
+		}
 
-			Ast::Key(label, point) => {
-				let point = self.map(point, parent_eid)?;
-				self.envs[parent_eid].insert(label.clone(), point.clone());
-				// .insert(label.clone(), (parent_eid, point.clone()));
-				Ok(Ast::Key(label.clone(), Box::new(point)))
+		let mut expanded_points = Vec::new();
+		for point in points {
+			match point {
+				// {..a, b: 1} the points of a are points of this graph
+				Ast::Op1(Name::Bleed, source) => {
+					let source = self.map(source, eid)?;
+					for alias in self.aliases(&source)? {
+						let alias = self.alias(alias, eid);
+						join(&mut expanded_points, alias);
+					}
+				}
+				_ => {
+					let point = self.map(point, eid)?;
+					if points.iter().any(is_spread) {
+						join(&mut expanded_points, point);
+					} else {
+						expanded_points.push(point);
+					}
+				}
 			}
+		}
+		self.ordinals(&expanded_points, eid);
+		Ok(expanded_points)
+	}
 
-			Ast::Apply(source, modifier) => {
-				let index = self.envs.len();
-				let mut env = BTreeMap::new();
-				env.insert("<parent>".to_string(), Ast::Parent(parent_eid));
-				self.envs.push(env);
-				// removed the concept of ordinal args, because I could not create an intuative set of replacement rules, especially with intermediary points
-				// removed the concept of the intermediary points, because it also creates confusion
-
-				// ref -> expander::ref
-				let source = self.map(source, parent_eid)?;
-				// ref -> ast
-				let source = self.deref(&source)?;
-
-				let s_points = match &source {
-					Ast::Graph(_eid, points) => (*points).clone(),
-					_ => Vec::new(),
-				};
+	// {1, 2, bob: 10} -> "0": 1, "1": 2, "2": bob
+	fn ordinals(&mut self, points: &[Ast], eid: EID) {
+		for (i, point) in points.iter().enumerate() {
+			let point = match point {
+				Ast::Key(label, _) => Ast::Ref(eid, label.clone()),
+				_ => point.clone(),
+			};
+			self.envs.insert(eid, &i.to_string(), point, Kind::Ordinal);
+		}
+	}
 
-				let m_points = match (**modifier).clone() {
-					Ast::Graph(_, points) => points,
-					_ => Vec::new(),
-				};
+	fn map(&mut self, ast: &Ast, parent_eid: EID) -> Result<Ast, String> {
+		match ast {
+			Ast::Graph(_, points) => {
+				let index = self.scope(parent_eid);
+				let expanded_points = self.map_points(points, index)?;
 
-				// throw error for modifier points that don't exist in source
-				for m_point in &m_points {
-					if !has_key(&s_points, &m_point) {
+				// a match graph is only arms, first guard that holds wins
+				if points.iter().any(is_arm) {
+					if let Some(point) = points.iter().find(|p| !is_arm(p))
+					{
 						return Err(format!(
-							"{:?} does not exist in {:?}",
-							&m_point, &s_points
+							"{:?} is not an arm, every point of a match is guard -> value",
+							point
 						));
 					}
 				}
 
-				let mut points = Vec::new();
-				for s_point in s_points {
-					points.push(match get_key(&m_points, &s_point) {
-						Some(point) => point,
-						None => s_point.clone(),
-					});
-				}
+				Ok(Ast::Graph(index, expanded_points))
+			}
 
-				let mut expanded_points = Vec::new();
-				for point in points {
-					expanded_points.push(self.map(&point, index)?);
+			Ast::Key(label, _) if is_ordinal(label) => Err(format!(
+				"{}: is a position, positions are only replaced in an applied graph, like a {{{}: 5}}",
+				label, label
+			)),
+			Ast::Key(label, point) => {
+				// a labelled graph is a scope under its label
+				if let Ast::Graph(_, _) | Ast::Apply(_, _) = **point {
+					self.label = Some(label.clone());
 				}
+				// a pun, P { x } is P { x: x }, refers to the x around the
+				// graph, not to itself, and so does a value in parens
+				let outside =
+					self.outside.remove(&(parent_eid, label.clone()));
+				let eid = match &**point {
+					_ if outside => self.envs.scopes[parent_eid].parent,
+					Ast::Ref(_, other) if other == label => {
+						self.envs.scopes[parent_eid].parent
+					}
+					_ => parent_eid,
+				};
+				self.defining.push((parent_eid, label.clone()));
+				let point = self.map(point, eid);
+				self.defining.pop();
+				let point = point?;
+				self.label = None;
+				self.envs.insert(
+					parent_eid,
+					label,
+					point.clone(),
+					Kind::Point,
+				);
+				Ok(Ast::Key(label.clone(), Box::new(point)))
+			}
 
-				Ok(Ast::Graph(index, expanded_points))
+			Ast::Apply(source, modifier) => {
+				self.apply(source, modifier, parent_eid)
 			}
 
 			Ast::Ref(_, label) => {
 				// dbg!(self.lookup(label, parent));
-				dbg!(self.deref(&Ast::Ref(parent_eid, label.clone()))?);
+				self.deref(&Ast::Ref(parent_eid, label.clone()))?;
 				Ok(Ast::Ref(parent_eid, label.clone()))
 			}
 			Ast::Op2(name, left, right) => match name {
-				Name::Select => {
+				Name::Pattern => self.pattern(left, right, parent_eid),
+				// _ guards nothing, it matches anything, any other label
+				// holds when its point isn't 0 { x < 0 -> 0 - x, _ -> x }
+				Name::Arrow => {
+					let guard = match &**left {
+						Ast::Ref(_, label) if label == "_" => Ast::Nothing,
+						_ => self.map(left, parent_eid)?,
+					};
+					let right = self.map(right, parent_eid)?;
+					Ok(Ast::Op2(*name, Box::new(guard), Box::new(right)))
+				}
+				Name::Index => {
 					let left = self.map(left, parent_eid)?;
-					let left_ns = self.deref(&left)?;
-					let eid = get_eid(&left_ns)?;
-
-					// shouldn't need to map the right operand, it should
-					// always be a simple ref, just get the label and go
-					let right = self.map(right, eid)?;
-					let label = get_label(&right)?;
-					Ok(Ast::Ref(eid, label))
+					let right = self.selector(right, parent_eid)?;
+					Ok(Ast::Op2(*name, Box::new(left), Box::new(right)))
+				}
+				Name::Select => self.select(left, right, parent_eid),
+				// a .. b, the points of a then the points of b, when either
+				// is only known once reduced they are joined then
+				Name::Bleed => {
+					let left = self.map(left, parent_eid)?;
+					let right = self.map(right, parent_eid)?;
+					match (self.aliases(&left), self.aliases(&right)) {
+						(Ok(mut points), Ok(more)) => {
+							points.extend(more);
+							Ok(self.subgraph(points, parent_eid))
+						}
+						_ => Ok(Ast::Op2(
+							*name,
+							Box::new(left),
+							Box::new(right),
+						)),
+					}
 				}
 				_ => {
 					let left = self.map(left, parent_eid)?;
@@ -176,19 +565,952 @@ impl Parse {
 					Ok(Ast::Op2(*name, Box::new(left), Box::new(right)))
 				}
 			},
+			Ast::If(cond, then, other) => Ok(Ast::If(
+				Box::new(self.map(cond, parent_eid)?),
+				Box::new(self.map(then, parent_eid)?),
+				Box::new(self.map(other, parent_eid)?),
+			)),
+			Ast::Range(lo_op, lo, hi_op, hi) => Ok(Ast::Range(
+				*lo_op,
+				Box::new(self.map(lo, parent_eid)?),
+				*hi_op,
+				Box::new(self.map(hi, parent_eid)?),
+			)),
+			Ast::Op1(Name::Bleed, source) => Err(format!(
+				"..{:?} only spreads into a graph, like {{..a, b: 1}}",
+				source
+			)),
+			// (<7) is 0 to 6, (>7) is 8 and every Integer after it
+			Ast::Op1(name, n) if is_order(*name) => {
+				self.map(&iota(*name, n), parent_eid)
+			}
+			Ast::Op1(
+				name @ (Name::Shape
+				| Name::Not
+				| Name::Index
+				| Name::Add
+				| Name::Sub
+				| Name::Div),
+				operand,
+			) => Ok(Ast::Op1(
+				*name,
+				Box::new(self.map(operand, parent_eid)?),
+			)),
 			Ast::Op1(name, operand) => Ok(Ast::Nothing),
 			Ast::Op0(name) => Ok(Ast::Nothing),
-			Ast::Space(sizes) => Ok(Ast::Nothing),
+			Ast::Space(sizes) => {
+				let mut expanded_sizes = Vec::new();
+				for size in sizes {
+					// [Point] is parsed as [Point: Point], it sizes by
+					// Point, it doesn't declare it
+					let size = match size {
+						Ast::Key(_, point) => point,
+						_ => size,
+					};
+					expanded_sizes.push(self.map(size, parent_eid)?);
+				}
+				Ok(Ast::Space(expanded_sizes))
+			}
 
 			Ast::Decimal(i) => Ok(Ast::Decimal(i.clone())),
 			Ast::Integer(i) => Ok(Ast::Integer(i.clone())),
 			Ast::String(i) => Ok(Ast::String(i.clone())),
-			Ast::Clock(t, i) => Ok(Ast::Clock(t.clone(), i.clone())),
-			Ast::Size(i) => Ok(Ast::Size(i.clone())),
+			Ast::Clock(t, i) => Ok(Ast::Clock(t.clone(), *i)),
+			Ast::Size(i) => Ok(Ast::Size(*i)),
 			Ast::Nothing => Ok(Ast::Nothing),
-			x => return Err(format!("No map match defined for {:?}", x)),
 		}
 	}
+	// Base {x: 1} is Base with x replaced, Base {x: 1} {y: 2} applies
+	// both. a graph replacing a graph is applied to it, Config {net:
+	// {port: 80}} and Config {net.port: 80} keep the rest of net. points
+	// Base doesn't have are added with +, Base {+z: 3}. Base(x) replaces
+	// the first point of Base with the x around it
+	fn apply(
+		&mut self,
+		source: &Ast,
+		modifier: &Ast,
+		parent_eid: EID,
+	) -> Result<Ast, String> {
+		let index = self.scope(parent_eid);
+		// removed the concept of ordinal args, because I could not create an intuative set of replacement rules, especially with intermediary points
+		// removed the concept of the intermediary points, because it also creates confusion
+
+		let name = name(source);
+		// ref -> expander::ref
+		let source = self.map(source, parent_eid)?;
+		self.defined(&source, "a graph can't be applied to itself")?;
+		// ref -> ast, an applied graph is already a graph
+		let value = match &source {
+			Ast::Ref(_, _) => self.deref(&source)?.clone(),
+			x => x.clone(),
+		};
+		self.applied(index, &name, &source, value, modifier)
+	}
+
+	// what applying means depends on what's applied to
+	fn applied(
+		&mut self,
+		index: EID,
+		name: &str,
+		source: &Ast,
+		value: Ast,
+		modifier: &Ast,
+	) -> Result<Ast, String> {
+		// Sqrt(disc) is Sqrt {n: disc}, a value replaces the first point
+		let m_points = match modifier {
+			Ast::Graph(_, points) => points.clone(),
+			x => {
+				let first = match &value {
+					Ast::Graph(_, points) => {
+						points.iter().find_map(|point| match point {
+							Ast::Key(label, _)
+								if !label.starts_with('<') =>
+							{
+								Some(label.clone())
+							}
+							_ => None,
+						})
+					}
+					_ => None,
+				};
+				let label = first.unwrap_or_else(|| "0".to_string());
+				self.outside.insert((index, label.clone()));
+				vec![Ast::Key(label, Box::new(x.clone()))]
+			}
+		};
+		match value {
+			Ast::Graph(_, points) if points.iter().any(is_arm) => {
+				Err(format!(
+					"{} is a match, it has arms, not points to replace",
+					name
+				))
+			}
+			Ast::Graph(s_eid, s_points) => {
+				let s_points: Vec<Ast> = s_points
+					.into_iter()
+					.map(|point| self.unshared(s_eid, point))
+					.collect();
+				let changed = changed(&s_points, &m_points);
+				let shared =
+					self.shareable(s_eid, index, &s_points, &changed);
+				let points = self.overrides(name, s_points, m_points)?;
+				if shared.is_empty() {
+					let expanded_points = self.map_points(&points, index)?;
+					return Ok(Ast::Graph(index, expanded_points));
+				}
+				self.sizes.push(Share {
+					instance: self.envs.scopes[index].path.clone(),
+					source: self.envs.scopes[s_eid].path.clone(),
+					labels: shared.iter().cloned().collect(),
+					points: points.len(),
+					nodes: points
+						.iter()
+						.filter(|point| match point {
+							Ast::Key(label, _) => shared.contains(label),
+							_ => false,
+						})
+						.map(|point| size(point) - 2)
+						.sum(),
+				});
+				match self.strategy {
+					Strategy::Full => {
+						let expanded_points =
+							self.map_points(&points, index)?;
+						Ok(Ast::Graph(index, expanded_points))
+					}
+					Strategy::Selective => {
+						self.instance(points, s_eid, index, &shared)
+					}
+				}
+			}
+			// [4 i32]! {1, 2, 3, 4} {1: 20} is still sized
+			Ast::Op2(Name::Sizer, left, right) => {
+				let (space, graph) = match *left {
+					Ast::Space(_) => (left, right),
+					_ => (right, left),
+				};
+				let graph =
+					self.applied(index, name, source, *graph, modifier)?;
+				Ok(Ast::Op2(Name::Sizer, space, Box::new(graph)))
+			}
+			// [4 i32] {1: 5} is the array with its point at 1 replaced
+			Ast::Space(sizes)
+				if sizes
+					.iter()
+					.any(|size| !matches!(size, Ast::Size(_))) =>
+			{
+				let count = match &sizes[0] {
+					Ast::Integer(n) => {
+						n.replace('\'', "").parse::<usize>().ok()
+					}
+					_ => None,
+				};
+				let count = count.ok_or_else(|| {
+					format!(
+						"{} is sized by {:?}, an array is only applied to when its count is an Integer",
+						name, sizes[0]
+					)
+				})?;
+				let mut points: Vec<Ast> = (0..count)
+					.map(|i| {
+						Ast::Op2(
+							Name::Index,
+							Box::new(source.clone()),
+							Box::new(Ast::Integer(i.to_string())),
+						)
+					})
+					.collect();
+				for m_point in m_points {
+					let (position, value) = match m_point {
+						Ast::Key(label, value) if is_ordinal(&label) => (
+							label.parse::<usize>().unwrap_or(count),
+							*value,
+						),
+						Ast::Key(label, _) => {
+							return Err(format!(
+								"{} is an array, its points are replaced by position, like {} {{1: 5}}, not by label {}",
+								name, name, label
+							))
+						}
+						x => {
+							return Err(format!(
+								"{:?} is not a point, {} is applied to like {} {{1: 5}}",
+								x, name, name
+							))
+						}
+					};
+					if position >= count {
+						return Err(format!(
+							"position {} is out of bounds of {}, an array of {}",
+							position, name, count
+						));
+					}
+					points[position] = value;
+				}
+				let expanded_points = self.map_points(&points, index)?;
+				Ok(Ast::Graph(index, expanded_points))
+			}
+			Ast::Space(_) => Err(format!(
+				"TypeError: {} is a single value, only graphs and arrays are applied to, write the value instead",
+				name
+			)),
+			Ast::Integer(_) => Err(literal(name, "an Integer")),
+			Ast::Decimal(_) => Err(literal(name, "a Decimal")),
+			Ast::String(_) => Err(literal(name, "a String")),
+			Ast::Clock(_, _) => Err(literal(name, "a Clock")),
+			_ => Err(format!(
+				"{} is only known once reduced, apply to a graph or a sized array",
+				name
+			)),
+		}
+	}
+
+	// a variable graph is shared, an instance refers to the points of its
+	// source that its overrides don't reach instead of copying them. a
+	// constant graph is copied, it folds away once reduced
+	fn instance(
+		&mut self,
+		points: Vec<Ast>,
+		s_eid: EID,
+		index: EID,
+		shared: &BTreeSet<String>,
+	) -> Result<Ast, String> {
+		for point in &points {
+			match point {
+				Ast::Key(label, value) if shared.contains(label) => {
+					self.alias(Ast::Ref(s_eid, label.clone()), index);
+					self.shared.insert(
+						self.envs.path(index, label),
+						*value.clone(),
+					);
+				}
+				_ => {
+					for label in declared_labels(point) {
+						self.envs.insert(
+							index,
+							&label,
+							Ast::Nothing,
+							Kind::Point,
+						);
+					}
+				}
+			}
+		}
+		let mut expanded_points = Vec::new();
+		for point in points {
+			match point {
+				Ast::Key(label, _) if shared.contains(&label) => {
+					expanded_points.push(Ast::Key(
+						label.clone(),
+						Box::new(Ast::Ref(s_eid, label)),
+					));
+				}
+				_ => expanded_points.push(self.map(&point, index)?),
+			}
+		}
+		self.ordinals(&expanded_points, index);
+		Ok(Ast::Graph(index, expanded_points))
+	}
+
+	// a shared point is copied from where it was shared from once an
+	// override of its own reaches it
+	fn unshared(&self, eid: EID, point: Ast) -> Ast {
+		match point {
+			Ast::Key(label, value) => {
+				let value =
+					match self.shared.get(&self.envs.path(eid, &label)) {
+						Some(source) => source.clone(),
+						None => *value,
+					};
+				Ast::Key(label, Box::new(value))
+			}
+			_ => point,
+		}
+	}
+
+	// the labels of the points of a variable graph an instance can refer
+	// to, none of the points they need are replaced or added, an input,
+	// clock or register of the graph, or found elsewhere from the instance
+	fn shareable(
+		&self,
+		s_eid: EID,
+		index: EID,
+		s_points: &[Ast],
+		changed: &BTreeSet<String>,
+	) -> BTreeSet<String> {
+		let mut shared = BTreeSet::new();
+		if !self.variable(s_eid) {
+			return shared;
+		}
+		for point in s_points {
+			match point {
+				Ast::Key(label, _)
+					if !self.tied(s_eid, index, label, changed) =>
+				{
+					shared.insert(label.clone());
+				}
+				_ => {}
+			}
+		}
+		shared
+	}
+
+	// a graph that needs an input, a clock or a register anywhere
+	fn variable(&self, eid: EID) -> bool {
+		let mut stack: Vec<String> = self
+			.envs
+			.points(eid)
+			.map(|(label, _)| self.envs.path(eid, label))
+			.collect();
+		let mut seen = BTreeSet::new();
+		while let Some(path) = stack.pop() {
+			if !seen.insert(path.clone()) {
+				continue;
+			}
+			if let Some(symbol) = self.envs.at(&path) {
+				if source(&symbol.point) {
+					return true;
+				}
+				self.needs(&symbol.point, &mut stack);
+			}
+		}
+		false
+	}
+
+	fn tied(
+		&self,
+		s_eid: EID,
+		index: EID,
+		label: &str,
+		changed: &BTreeSet<String>,
+	) -> bool {
+		let prefix = format!("{}.", self.envs.scopes[s_eid].path);
+		let mut stack = vec![self.envs.path(s_eid, label)];
+		let mut seen = BTreeSet::new();
+		while let Some(path) = stack.pop() {
+			if !seen.insert(path.clone()) {
+				continue;
+			}
+			let symbol = match self.envs.at(&path) {
+				Some(symbol) => symbol,
+				None => continue,
+			};
+			match path.strip_prefix(&prefix) {
+				Some(rest) => {
+					let top = rest.split('.').next().unwrap_or(rest);
+					if changed.contains(top) || source(&symbol.point) {
+						return true;
+					}
+					self.needs(&symbol.point, &mut stack);
+				}
+				None => {
+					let label = path.rsplit('.').next().unwrap_or(&path);
+					match find(&self.envs, label, index) {
+						Ok((eid, _)) if eid == symbol.parent => {}
+						_ => return true,
+					}
+				}
+			}
+		}
+		false
+	}
+
+	// the points of an applied graph, replaced by label or position, and
+	// extended
+	fn overrides(
+		&mut self,
+		name: &str,
+		s_points: Vec<Ast>,
+		m_points: Vec<Ast>,
+	) -> Result<Vec<Ast>, String> {
+		let labels: Vec<&String> = s_points
+			.iter()
+			.filter_map(|point| match point {
+				Ast::Key(label, _) if !label.starts_with('<') => {
+					Some(label)
+				}
+				_ => None,
+			})
+			.collect();
+
+		let mut extensions = Vec::new();
+		let mut overrides = Vec::new();
+		for m_point in m_points {
+			match m_point {
+				Ast::Op1(Name::Add, point) => match *point {
+					Ast::Key(label, _) if labels.contains(&&label) => {
+						return Err(format!(
+							"Point {} is already a point of {}, replace it with {}: ..",
+							label, name, label
+						))
+					}
+					Ast::Key(label, _) if is_ordinal(&label) => {
+						return Err(format!(
+							"+{}: adds a point by position, points are added by label, like +z: 3",
+							label
+						))
+					}
+					point @ Ast::Key(_, _) => extensions.push(point),
+					x => {
+						return Err(format!(
+							"{:?} is not a point, add points like +z: 3",
+							x
+						))
+					}
+				},
+				// a position replaces the point there, named or not
+				Ast::Key(ref label, _) if is_ordinal(label) => {
+					let position = label.parse::<usize>().unwrap_or(usize::MAX);
+					if position >= s_points.len() {
+						return Err(format!(
+							"position {} is out of bounds of {}, a graph of {} points",
+							label,
+							name,
+							s_points.len()
+						));
+					}
+					overrides.push((Some(position), m_point));
+				}
+				Ast::Key(ref label, _) if !labels.contains(&label) => {
+					let mut msg = format!(
+						"Point {} is not a point of {}, it has {}",
+						label,
+						name,
+						labels
+							.iter()
+							.map(|l| l.as_str())
+							.collect::<Vec<&str>>()
+							.join(", ")
+					);
+					let near = labels
+						.iter()
+						.filter(|l| typo(label, l))
+						.min_by_key(|l| distance(label, l));
+					msg.push_str(&match near {
+						Some(near) => format!(
+							", did you mean {}, or add it with +{}: ..",
+							near, label
+						),
+						None => format!(", add it with +{}: ..", label),
+					});
+					return Err(msg);
+				}
+				point => overrides.push((None, point)),
+			}
+		}
+
+		let mut points = Vec::new();
+		for (i, s_point) in s_points.into_iter().enumerate() {
+			let (label, mut value) = match s_point {
+				Ast::Key(label, value) => (Some(label), *value),
+				point => (None, point),
+			};
+			for (position, m_point) in &overrides {
+				match m_point {
+					Ast::Key(l, m_value)
+						if *position == Some(i)
+							|| (position.is_none()
+								&& Some(l) == label.as_ref()) =>
+					{
+						value = self.merge(value, (**m_value).clone());
+					}
+					_ => {}
+				}
+			}
+			points.push(match label {
+				Some(label) => Ast::Key(label, Box::new(value)),
+				None => value,
+			});
+		}
+		points.extend(extensions);
+		Ok(points)
+	}
+
+	// a graph replacing a graph is applied to it, anything else replaces
+	fn merge(&self, value: Ast, modifier: Ast) -> Ast {
+		let graph = match &value {
+			Ast::Graph(_, points) => !points.iter().any(is_arm),
+			Ast::Apply(_, _) => true,
+			Ast::Ref(_, _) => {
+				matches!(self.deref(&value), Ok(Ast::Graph(_, points)) if !points.iter().any(is_arm))
+			}
+			_ => false,
+		};
+		match modifier {
+			Ast::Graph(_, ref points)
+				if graph && !points.iter().any(is_arm) =>
+			{
+				Ast::Apply(Box::new(value), Box::new(modifier))
+			}
+			_ => modifier,
+		}
+	}
+
+	// Point {x, y} ~ my_point: Point {x: 10, y: 20}
+	// binds the points of the target graph that the pattern names into the
+	// enclosing graph, the whole expression is still the target
+	fn pattern(
+		&mut self,
+		pattern: &Ast,
+		target: &Ast,
+		parent_eid: EID,
+	) -> Result<Ast, String> {
+		let target = self.map(target, parent_eid)?;
+		let target_eid = self.target_eid(&target)?;
+
+		let (typ, binds) = match pattern {
+			Ast::Apply(source, modifier) => {
+				let source = self.map(source, parent_eid)?;
+				let typ = match self.deref(&source)? {
+					Ast::Graph(eid, _) => self.labels(*eid),
+					x => return Err(format!("{:?} is not a Graph", x)),
+				};
+				(typ, pattern_binds(modifier)?)
+			}
+			Ast::Graph(_, _) => {
+				let binds = pattern_binds(pattern)?;
+				(
+					binds.iter().map(|(label, _)| label.clone()).collect(),
+					binds,
+				)
+			}
+			x => return Err(format!("{:?} is not a pattern", x)),
+		};
+
+		for (label, _) in &binds {
+			if !typ.contains(label) {
+				return Err(format!(
+					"TypeError: pattern point {} is not one of {:?}",
+					label, typ
+				));
+			}
+		}
+
+		// structural typing, the target must have exactly the labels of
+		// the pattern's graph
+		let labels = self.labels(target_eid);
+		let missing: Vec<&String> =
+			typ.iter().filter(|l| !labels.contains(l)).collect();
+		let extra: Vec<&String> =
+			labels.iter().filter(|l| !typ.contains(l)).collect();
+		if !missing.is_empty() || !extra.is_empty() {
+			return Err(format!(
+				"TypeError: target of pattern {:?} is missing {:?} and has extra {:?}",
+				typ, missing, extra
+			));
+		}
+
+		for (label, name) in binds {
+			self.envs.insert(
+				parent_eid,
+				&name,
+				Ast::Ref(target_eid, label),
+				Kind::Alias,
+			);
+		}
+
+		Ok(target)
+	}
+
+	// what follows #, an index or a range of indices
+	// 	array#[1]		array#(i + 1)
+	// 	array#[> 10]	array#[10 < 20]	array#[10 < i < 20]
+	// the label of a labelled range is bound to the indices it selects
+	fn selector(
+		&mut self,
+		selector: &Ast,
+		parent_eid: EID,
+	) -> Result<Ast, String> {
+		let point = match selector {
+			// [n] is parsed as [n: n], it selects by n, it doesn't declare it
+			Ast::Space(points) if points.len() == 1 => match &points[0] {
+				Ast::Key(_, point) => &**point,
+				point => point,
+			},
+			Ast::Space(points) => {
+				return Err(format!(
+					"{:?} is not a selector, select one index or one range",
+					points
+				))
+			}
+			_ => return self.map(selector, parent_eid),
+		};
+		let range = match point {
+			Ast::Op1(name, n) if is_order(*name) => iota(*name, n),
+			Ast::Op2(hi_op, inner, hi) if is_order(*hi_op) => {
+				match &**inner {
+					Ast::Op2(lo_op, lo, i) if is_order(*lo_op) => {
+						let label = match &**i {
+							Ast::Ref(_, label) => label.clone(),
+							x => {
+								return Err(format!(
+								"{:?} is not a label, a range is labelled like 10 < i < 20",
+								x
+							))
+							}
+						};
+						let range = Ast::Range(
+							*lo_op,
+							lo.clone(),
+							*hi_op,
+							hi.clone(),
+						);
+						let range = self.map(&range, parent_eid)?;
+						self.envs.insert(
+							parent_eid,
+							&label,
+							range.clone(),
+							Kind::Range,
+						);
+						return check_range(range);
+					}
+					_ => Ast::Range(
+						*hi_op,
+						inner.clone(),
+						*hi_op,
+						hi.clone(),
+					),
+				}
+			}
+			_ => return self.map(point, parent_eid),
+		};
+		let range = self.map(&range, parent_eid)?;
+		check_range(range)
+	}
+
+	// base.jef		a point by label
+	// base.10		a point by position, named or not
+	// base.{1, 2}	a new graph of the points at those positions
+	// base.[jef, bob]	a new graph of the points with those labels
+	// base.(i + 1) is parsed as base#(i + 1)
+	fn select(
+		&mut self,
+		source: &Ast,
+		selector: &Ast,
+		parent_eid: EID,
+	) -> Result<Ast, String> {
+		let source = self.map(source, parent_eid)?;
+		// a.c inside a: {b: a.c, c: 1} is c, a has no value yet but its
+		// labels are declared
+		if let (Some(eid), Ast::Ref(_, label)) =
+			(self.declaring(&source), selector)
+		{
+			return match self.envs.get(eid, label) {
+				Some(_) => Ok(Ast::Ref(eid, label.clone())),
+				None => Err(missing(&self.envs, &source, label, eid)),
+			};
+		}
+		self.defined(&source, "a graph can't be selected from while it's being defined, other than by the label of one of its own points")?;
+		let eid = match &source {
+			Ast::Graph(eid, _) => *eid,
+			Ast::Ref(_, _) => match self.deref(&source)? {
+				Ast::Graph(eid, _) => *eid,
+				x => {
+					return self.select_reduced(
+						source.clone(),
+						x,
+						selector,
+					)
+				}
+			},
+			x => return self.select_reduced(source.clone(), x, selector),
+		};
+
+		match selector {
+			// only the graph's own labels, not those of the scopes
+			// around it
+			Ast::Ref(_, label) => match self.envs.get(eid, label) {
+				Some(_) => Ok(Ast::Ref(eid, label.clone())),
+				None => Err(missing(&self.envs, &source, label, eid)),
+			},
+			Ast::Integer(_) => self.position(eid, selector),
+			Ast::Graph(_, positions) => {
+				let mut selected = Vec::new();
+				for position in positions {
+					selected.push(self.position(eid, position)?);
+				}
+				Ok(self.subgraph(selected, parent_eid))
+			}
+			Ast::Space(labels) => {
+				let mut selected = Vec::new();
+				for label in labels {
+					// a bare label in a list is parsed as jef: jef
+					let label = match label {
+						Ast::Key(label, _) => label.clone(),
+						_ => get_label(label)?,
+					};
+					if self.envs.get(eid, &label).is_none() {
+						return Err(missing(&self.envs, &source, &label, eid));
+					}
+					selected.push(Ast::Ref(eid, label));
+				}
+				Ok(self.subgraph(selected, parent_eid))
+			}
+			x => Err(format!(
+				"{:?} is not a selector, select like base.jef, base.10, base.{{1, 2}} or base.[jef, bob]",
+				x
+			)),
+		}
+	}
+
+	// the point at a position of a graph, by the label of a named point
+	fn position(&self, eid: EID, position: &Ast) -> Result<Ast, String> {
+		let ordinal = match position {
+			Ast::Integer(n) => n.replace('\'', ""),
+			x => {
+				return Err(format!(
+					"{:?} is not a position, positions are Integers",
+					x
+				))
+			}
+		};
+		match self.envs.get(eid, &ordinal) {
+			Some(Ast::Ref(_, label)) if !is_ordinal(label) => {
+				Ok(Ast::Ref(eid, label.clone()))
+			}
+			Some(_) => Ok(Ast::Ref(eid, ordinal)),
+			None => Err(format!(
+				"position {} is out of bounds of a graph of {} points",
+				ordinal,
+				self.envs
+					.points(eid)
+					.filter(|(l, _)| is_ordinal(l))
+					.count()
+			)),
+		}
+	}
+
+	// a graph only known once reduced, like a reshaped array, is only
+	// selected by position
+	fn select_reduced(
+		&self,
+		source: Ast,
+		point: &Ast,
+		selector: &Ast,
+	) -> Result<Ast, String> {
+		match selector {
+			Ast::Integer(_) => Ok(Ast::Op2(
+				Name::Index,
+				Box::new(source),
+				Box::new(selector.clone()),
+			)),
+			_ => Err(format!(
+				"{:?} is not a Graph, only graphs have points to select",
+				point
+			)),
+		}
+	}
+
+	// a graph of selected points, labelled points keep their labels
+	fn subgraph(&mut self, selected: Vec<Ast>, parent_eid: EID) -> Ast {
+		let index = self.scope(parent_eid);
+		let mut points = Vec::new();
+		for point in selected {
+			let point = self.alias(point, index);
+			join(&mut points, point);
+		}
+		self.ordinals(&points, index);
+		Ast::Graph(index, points)
+	}
+
+	// a ref to a labelled point of another graph is a point of graph eid
+	// under the same label
+	fn alias(&mut self, point: Ast, eid: EID) -> Ast {
+		match point {
+			Ast::Ref(_, ref label) if !is_ordinal(label) => {
+				self.envs.insert(eid, label, point.clone(), Kind::Alias);
+				Ast::Key(label.clone(), Box::new(point))
+			}
+			_ => point,
+		}
+	}
+
+	// refs to every point of a graph, by label or by index
+	fn aliases(&self, source: &Ast) -> Result<Vec<Ast>, String> {
+		let (eid, points) = match source {
+			Ast::Graph(eid, points) => (*eid, points),
+			Ast::Ref(_, _) => match self.deref(source)? {
+				Ast::Graph(eid, points) => (*eid, points),
+				x => return Err(format!("{:?} is not a Graph", x)),
+			},
+			x => return Err(format!("{:?} is not a Graph", x)),
+		};
+		Ok(points
+			.iter()
+			.enumerate()
+			.map(|(i, point)| match point {
+				Ast::Key(label, _) => Ast::Ref(eid, label.clone()),
+				_ => Ast::Ref(eid, i.to_string()),
+			})
+			.collect())
+	}
+
+	// the graph a pattern destructures, through labels and sizers
+	fn target_eid(&self, ast: &Ast) -> Result<EID, String> {
+		match ast {
+			Ast::Graph(eid, _) => Ok(*eid),
+			Ast::Key(_, point) => self.target_eid(point),
+			Ast::Ref(_, _) => self.target_eid(self.deref(ast)?),
+			Ast::Op2(Name::Sizer, left, right) => match **left {
+				Ast::Space(_) => self.target_eid(right),
+				_ => self.target_eid(left),
+			},
+			x => Err(format!("TypeError: {:?} is not a Graph", x)),
+		}
+	}
+
+	fn labels(&self, eid: EID) -> Vec<String> {
+		self.envs
+			.points(eid)
+			.filter(|(label, _)| {
+				!label.starts_with('<') && !is_ordinal(label)
+			})
+			.map(|(label, _)| label.to_string())
+			.collect()
+	}
+
+	// the scope of a graph that's still being mapped, its labels are
+	// declared before its points are mapped
+	fn declaring(&self, source: &Ast) -> Option<EID> {
+		let (eid, label) = match source {
+			Ast::Ref(eid, label) => {
+				(find(&self.envs, label, *eid).ok()?.0, label)
+			}
+			_ => return None,
+		};
+		if !self.defining.iter().any(|(e, l)| *e == eid && l == label) {
+			return None;
+		}
+		self.envs.paths.get(&self.envs.path(eid, label)).copied()
+	}
+
+	// a graph applied or selected from while it's still being mapped has
+	// no points yet, a: a {..} or a: {b: a.1}
+	fn defined(&self, source: &Ast, why: &str) -> Result<(), String> {
+		let (eid, label) = match source {
+			Ast::Ref(eid, label) => match find(&self.envs, label, *eid) {
+				Ok((eid, _)) => (eid, label),
+				Err(_) => return Ok(()),
+			},
+			_ => return Ok(()),
+		};
+		let start = self
+			.defining
+			.iter()
+			.position(|(e, l)| *e == eid && l == label);
+		match start {
+			Some(start) => {
+				let mut cycle: Vec<String> = self.defining[start..]
+					.iter()
+					.map(|(eid, label)| self.envs.path(*eid, label))
+					.collect();
+				cycle.push(self.envs.path(eid, label));
+				Err(format!("Cycle: {}, {}", self.spanned(&cycle), why))
+			}
+			None => Ok(()),
+		}
+	}
+
+	// a point whose value needs its own value has none
+	fn cycles(&self) -> Result<(), String> {
+		let mut done = BTreeSet::new();
+		for path in self.envs.table.keys() {
+			let mut stack = Vec::new();
+			if let Some(cycle) = self.visit(path, &mut stack, &mut done) {
+				return Err(format!(
+					"Cycle: {}, a point can't depend on itself",
+					self.spanned(&cycle)
+				));
+			}
+		}
+		Ok(())
+	}
+
+	fn visit(
+		&self,
+		path: &str,
+		stack: &mut Vec<String>,
+		done: &mut BTreeSet<String>,
+	) -> Option<Vec<String>> {
+		if done.contains(path) {
+			return None;
+		}
+		if let Some(start) = stack.iter().position(|p| p == path) {
+			let mut cycle = stack[start..].to_vec();
+			cycle.push(path.to_string());
+			return Some(cycle);
+		}
+		let symbol = self.envs.at(path)?;
+		let mut deps = Vec::new();
+		self.envs.deps(&symbol.point, false, &mut deps);
+		stack.push(path.to_string());
+		for dep in deps {
+			if let Some(cycle) = self.visit(&dep, stack, done) {
+				return Some(cycle);
+			}
+		}
+		stack.pop();
+		done.insert(path.to_string());
+		None
+	}
+
+	// the paths of the points a point needs, the value of a register too
+	fn needs(&self, ast: &Ast, needs: &mut Vec<String>) {
+		self.envs.deps(ast, true, needs)
+	}
+
+	// root.a (line 1) -> root.b (line 2) -> root.a (line 1)
+	fn spanned(&self, paths: &[String]) -> String {
+		paths
+			.iter()
+			.map(|path| match self.spans.get(path) {
+				Some(line) => format!("{} (line {})", path, line),
+				None => path.clone(),
+			})
+			.collect::<Vec<String>>()
+			.join(" -> ")
+	}
+
 	// fn deref(&self, ref_: &Ast) -> Result<&Item, String> {
 	fn deref(&self, ref_: &Ast) -> Result<&Ast, String> {
 		match (*ref_).clone() {
@@ -198,26 +1520,322 @@ impl Parse {
 	}
 
 	// fn lookup(&self, label: &String, eid: EID) -> Result<&Item, String> {
-	fn lookup(&self, label: &String, eid: EID) -> Result<&Ast, String> {
-		if eid == 0 {
-			return Err(format!("Point {} is undefined", label));
+	fn lookup(&self, label: &str, eid: EID) -> Result<&Ast, String> {
+		let (_, point) = find(&self.envs, label, eid)?;
+		Ok(point)
+	}
+}
+
+// walks up from env eid until label is found, returns the eid of the env
+// that defines it along with its point
+pub fn find<'a>(
+	envs: &'a Maps,
+	label: &str,
+	eid: EID,
+) -> Result<(EID, &'a Ast), String> {
+	let mut scope = eid;
+	while scope != 0 {
+		if let Some(result) = envs.get(scope, label) {
+			return Ok((scope, result));
+		}
+		scope = envs.parent(scope);
+	}
+	Err(undefined(envs, label, eid))
+}
+
+// where a label was looked for, the labels it may be a typo of, and the
+// graphs that have it but aren't in scope
+//	Point jef is undefined, searched root.q, root and prelude
+//	did you mean jeff (root.q)?
+//	bob has a jef, select it with bob.jef
+fn undefined(envs: &Maps, label: &str, eid: EID) -> String {
+	let mut searched = Vec::new();
+	let mut scope = eid;
+	while scope != 0 {
+		searched.push(scope);
+		scope = envs.parent(scope);
+	}
+	let paths: Vec<&str> = searched
+		.iter()
+		.map(|e| envs.scopes[*e].path.as_str())
+		.collect();
+	let mut msg = format!("Point {} is undefined, searched ", label);
+	msg.push_str(&match paths.split_last() {
+		Some((last, [])) => last.to_string(),
+		Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+		None => "nothing".to_string(),
+	});
+
+	// the closest labels first
+	let mut near = Vec::new();
+	for scope in &searched {
+		for (other, symbol) in envs.points(*scope) {
+			if symbol.kind == Kind::Ordinal || other.starts_with('<') {
+				continue;
+			}
+			if typo(label, other) {
+				let d = distance(label, other);
+				near.push((d, other, envs.scopes[*scope].path.as_str()));
+			}
+		}
+	}
+	near.sort();
+	if !near.is_empty() {
+		let near: Vec<String> = near
+			.iter()
+			.take(5)
+			.map(|(_, other, path)| format!("{} ({})", other, path))
+			.collect();
+		msg.push_str(&format!("\ndid you mean {}?", near.join(", ")));
+	}
+
+	// the label in a graph below one of the searched scopes, selected
+	// from the closest of them
+	let mut selects = Vec::new();
+	for (path, symbol) in &envs.table {
+		if symbol.kind == Kind::Ordinal
+			|| searched.contains(&symbol.parent)
+			|| !path.ends_with(&format!(".{}", label))
+		{
+			continue;
 		}
-		match self.envs[eid].get(label) {
-			Some(result) => Ok(result),
-			None => self.lookup(
-				label,
-				// self.envs[eid].get(&"<parent>".to_string()).unwrap().0,
-				get_parent_eid(
-					self.envs[eid].get(&"<parent>".to_string()).unwrap(),
-				),
-			),
+		let from = paths
+			.iter()
+			.find_map(|scope| path.strip_prefix(&format!("{}.", scope)));
+		if let Some(select) = from {
+			if !select.contains('<') {
+				let graph = &select[..select.len() - label.len() - 1];
+				selects.push(format!(
+					"\n{} has a {}, select it with {}",
+					graph, label, select
+				));
+			}
 		}
 	}
+	for select in selects.iter().take(5) {
+		msg.push_str(select);
+	}
+	msg
+}
+
+// a label a graph doesn't have, only the graph is searched, for the
+// labels it may be a typo of and the graphs in it that have it
+//	q has no label jef
+//	did you mean jeff?
+//	q.bob has a jef, select it with q.bob.jef
+fn missing(envs: &Maps, source: &Ast, label: &str, eid: EID) -> String {
+	let path = &envs.scopes[eid].path;
+	let graph = match source {
+		Ast::Ref(_, graph) => graph.as_str(),
+		_ => path.as_str(),
+	};
+	let mut msg = format!("{} has no label {}", graph, label);
+
+	let mut near: Vec<(usize, &str)> = envs
+		.points(eid)
+		.filter(|(other, symbol)| {
+			symbol.kind != Kind::Ordinal
+				&& !other.starts_with('<')
+				&& typo(label, other)
+		})
+		.map(|(other, _)| (distance(label, other), other))
+		.collect();
+	near.sort();
+	if !near.is_empty() {
+		let near: Vec<&str> =
+			near.iter().take(5).map(|(_, other)| *other).collect();
+		msg.push_str(&format!("\ndid you mean {}?", near.join(", ")));
+	}
+
+	let prefix = format!("{}.", path);
+	let selects = envs.table.iter().filter_map(|(at, symbol)| {
+		let inner = at.strip_prefix(&prefix)?;
+		let select = format!("{}.{}", graph, inner);
+		match symbol.kind != Kind::Ordinal
+			&& !inner.contains('<')
+			&& inner.ends_with(&format!(".{}", label))
+		{
+			true => Some(select),
+			false => None,
+		}
+	});
+	for select in selects.take(5) {
+		let inner = &select[..select.len() - label.len() - 1];
+		msg.push_str(&format!(
+			"\n{} has a {}, select it with {}",
+			inner, label, select
+		));
+	}
+	msg
+}
+
+// a label is a typo of another when a third of it or less is different,
+// and not all of it
+fn typo(a: &str, b: &str) -> bool {
+	let d = distance(a, b);
+	d > 0 && d < a.len() && d <= (a.len().max(b.len()) / 3).max(1)
+}
+
+// the fewest single character edits that turn a into b
+fn distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let above = row[j + 1];
+			row[j + 1] = if ca == *cb {
+				diagonal
+			} else {
+				1 + diagonal.min(above).min(row[j])
+			};
+			diagonal = above;
+		}
+	}
+	row[b.len()]
+}
+
+// {x, y: b, z;} -> [(x, x), (y, b), (z, z)]
+// pairs of the label in the target, and the label it is bound as
+fn pattern_binds(pattern: &Ast) -> Result<Vec<(String, String)>, String> {
+	let points = match pattern {
+		Ast::Graph(_, points) => points,
+		x => return Err(format!("{:?} is not a pattern", x)),
+	};
+	let mut binds = Vec::new();
+	for point in points {
+		match point {
+			Ast::Key(label, name) => match &**name {
+				Ast::Ref(_, name) => {
+					binds.push((label.clone(), name.clone()))
+				}
+				Ast::Nothing => binds.push((label.clone(), label.clone())),
+				x => {
+					return Err(format!(
+						"{:?} is not a label, patterns can only bind labels",
+						x
+					))
+				}
+			},
+			x => {
+				return Err(format!(
+					"{:?} is not a label, patterns can only bind labels",
+					x
+				))
+			}
+		}
+	}
+	Ok(binds)
+}
+
+fn is_order(name: Name) -> bool {
+	matches!(name, Name::Lt | Name::Le | Name::Gt | Name::Ge)
+}
+
+// a range counts up with < and <=, or down with > and >=
+fn check_range(range: Ast) -> Result<Ast, String> {
+	match &range {
+		Ast::Range(Name::Lt | Name::Le, _, Name::Lt | Name::Le, _)
+		| Ast::Range(Name::Gt | Name::Ge, _, Name::Gt | Name::Ge, _) => {
+			Ok(range)
+		}
+		_ => Err(format!(
+			"{:?} is not a range, it must count either up or down",
+			range
+		)),
+	}
+}
+
+pub fn is_arm(point: &Ast) -> bool {
+	matches!(point, Ast::Op2(Name::Arrow, _, _))
+}
+
+// the range of a unary order, (> n) is n < i, (<= n) is i <= n
+fn iota(name: Name, n: &Ast) -> Ast {
+	let (n, nothing) = (Box::new(n.clone()), Box::new(Ast::Nothing));
+	match name {
+		Name::Gt => Ast::Range(Name::Lt, n, Name::Lt, nothing),
+		Name::Ge => Ast::Range(Name::Le, n, Name::Lt, nothing),
+		Name::Le => Ast::Range(Name::Lt, nothing, Name::Le, n),
+		_ => Ast::Range(Name::Lt, nothing, Name::Lt, n),
+	}
+}
+
+fn is_spread(point: &Ast) -> bool {
+	matches!(point, Ast::Op1(Name::Bleed, _))
+}
+
+// adds a point to a graph, a labelled point replaces the point with the
+// same label
+fn join(points: &mut Vec<Ast>, point: Ast) {
+	match points.iter().position(|p| key_match(p, &point)) {
+		Some(i) => points[i] = point,
+		None => points.push(point),
+	}
+}
+
+// the key of an unnamed point, its index
+pub fn is_ordinal(label: &str) -> bool {
+	!label.is_empty() && label.chars().all(|c| c.is_ascii_digit())
+}
+
+// labels a point adds to its graph
+fn declared_labels(point: &Ast) -> Vec<String> {
+	match point {
+		Ast::Key(label, _) => vec![label.clone()],
+		Ast::Op2(Name::Pattern, pattern, target) => {
+			let pattern = match &**pattern {
+				Ast::Apply(_, modifier) => modifier,
+				_ => pattern,
+			};
+			let mut labels: Vec<String> = match pattern_binds(pattern) {
+				Ok(binds) => {
+					binds.into_iter().map(|(_, name)| name).collect()
+				}
+				Err(_) => Vec::new(),
+			};
+			labels.extend(declared_labels(target));
+			labels
+		}
+		Ast::Op2(Name::Sizer, left, right) => {
+			let mut labels = declared_labels(left);
+			labels.extend(declared_labels(right));
+			labels
+		}
+		_ => Vec::new(),
+	}
+}
+
+fn literal(name: &str, typ: &str) -> String {
+	format!(
+		"TypeError: {} is {}, only graphs and arrays are applied to",
+		name, typ
+	)
 }
 
-fn get_parent_eid(ast: &Ast) -> EID {
+// how a source is written, for diagnostics, lib.Base
+fn name(ast: &Ast) -> String {
 	match ast {
-		Ast::Parent(eid) => *eid,
-		_ => panic!("get_parent_eid used on non-parent node"),
+		Ast::Ref(_, label) => label.clone(),
+		Ast::Op2(Name::Select, a, b) => format!("{}.{}", name(a), name(b)),
+		Ast::Integer(v) | Ast::Decimal(v) | Ast::Clock(v, _) => v.clone(),
+		Ast::String(v) => v.clone(),
+		// [4 i32]
+		Ast::Space(sizes) => format!(
+			"[{}]",
+			sizes
+				.iter()
+				.map(|size| match size {
+					Ast::Size(size) =>
+						format!("{:?}", size).to_lowercase(),
+					Ast::Integer(v) => v.clone(),
+					_ => "..".to_string(),
+				})
+				.collect::<Vec<String>>()
+				.join(" ")
+		),
+		Ast::Apply(source, _) => format!("{} {{..}}", name(source)),
+		_ => "the graph".to_string(),
 	}
 }
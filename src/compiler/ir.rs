@@ -0,0 +1,741 @@
+/*
+the intermediate representation, a typed dataflow graph of points
+
+a point is an operator over the points before it, so the dependencies of
+a point are its operands and every point comes after what it needs. each
+point has a type, and a memory slot when its value is stored, an input
+or a point computed while running. constants and graphs have no slot, a
+constant is written in place and a graph is the points it holds
+
+it's written as text, a .rplir file, and read back, a backend or the
+runtime can start from one instead of from source
+
+	rplir 1
+	reduced 9 points to 7, 2 joined, 0 dead
+
+	%0 i64 @0 = input i32
+	%1 i64 = int 2
+	%2 i64 @1 = op2 mul %0 %1
+	%3 graph[2] = graph %0 %2
+
+	loop %3 = mul %0 %1
+	label root.x = %0
+	label root.y = %2
+	root = %3
+
+a signal is memory. a register holds the value last latched into it, a
+latch is the value it takes when a clock or an input its signal is
+computed from fires, and a clock's value is its phase. all three have a
+slot, a register's type is the type of what's latched into it
+
+	%0 i64 @0 = clock 1s
+	%1 i64 @1 = reg
+	%2 i64 = int 1
+	%3 i64 @2 = op2 add %1 %2
+	%4 i64 @3 = latch %1 %0 %3
+*/
+
+use super::parser::IDX;
+use super::reducer::{operands, Point, Program};
+use super::tokenizer::{tokenizer, Kind, Name};
+use std::collections::BTreeMap;
+
+const VERSION: usize = 1;
+
+// the operators of op1, op2 and loop points
+const OPS: [Name; 16] = [
+	Name::Or,
+	Name::And,
+	Name::Not,
+	Name::Add,
+	Name::Sub,
+	Name::Mul,
+	Name::Div,
+	Name::Exp,
+	Name::Eq,
+	Name::Ne,
+	Name::Gt,
+	Name::Lt,
+	Name::Ge,
+	Name::Le,
+	Name::Shape,
+	Name::Index,
+];
+
+// the sizes an input is written in
+const SIZES: [Name; 16] = [
+	Name::I8,
+	Name::I16,
+	Name::I32,
+	Name::I64,
+	Name::I128,
+	Name::U8,
+	Name::U16,
+	Name::U32,
+	Name::U64,
+	Name::U128,
+	Name::F32,
+	Name::F64,
+	Name::F128,
+	Name::C8,
+	Name::C16,
+	Name::C32,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+	Integer,      // i64
+	Decimal,      // f64
+	Graph(usize), // graph[n], n points
+	Array(usize), // array[n], filtered, at most n points
+	Sequence,     // seq, never whole
+	Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ir {
+	pub code: Vec<Point>,
+	pub types: Vec<Type>,
+	pub slots: Vec<Option<usize>>,
+	pub labels: BTreeMap<String, IDX>, // points by path
+	pub each: BTreeMap<IDX, (Name, IDX, IDX)>, // elementwise graphs
+	pub root: IDX,
+	pub pushed: usize, // points reduced, before identical ones are joined
+	pub dead: usize,   // points removed, nothing kept needs them
+}
+
+impl Ir {
+	fn new(
+		code: Vec<Point>,
+		labels: BTreeMap<String, IDX>,
+		each: BTreeMap<IDX, (Name, IDX, IDX)>,
+		root: IDX,
+	) -> Self {
+		// a register comes before what's latched into it, so its type is
+		// found by going over the points again until none changes
+		let mut registers = BTreeMap::new();
+		let mut types: Vec<Type> = Vec::new();
+		for _ in 0..=code.len() {
+			types.clear();
+			for point in &code {
+				types.push(typ(&code, &types, &registers, point));
+			}
+			let mut latched = BTreeMap::new();
+			for point in &code {
+				if let Point::Latch(r, _, v) = point {
+					latched.insert(*r, types[*v]);
+				}
+			}
+			if latched == registers {
+				break;
+			}
+			registers = latched;
+		}
+		let mut slots = Vec::new();
+		let mut next = 0;
+		for point in &code {
+			slots.push(match point {
+				Point::Input(_)
+				| Point::Op1(_, _)
+				| Point::Op2(_, _, _)
+				| Point::If(_, _, _)
+				| Point::Index(_, _)
+				| Point::Filter(_, _)
+				| Point::Tally(_)
+				| Point::Clock(_)
+				| Point::Register
+				| Point::Latch(_, _, _) => {
+					next += 1;
+					Some(next - 1)
+				}
+				_ => None,
+			});
+		}
+		Ir {
+			code,
+			types,
+			slots,
+			labels,
+			each,
+			root,
+			pushed: 0,
+			dead: 0,
+		}
+	}
+}
+
+// a reduced program, every point under the paths that name it
+pub fn ir(program: &Program) -> Ir {
+	let labels = program
+		.points
+		.iter()
+		.map(|((eid, label), idx)| (program.envs.path(*eid, label), *idx))
+		.collect();
+	let mut ir = Ir::new(
+		program.code.clone(),
+		labels,
+		program.each.clone(),
+		program.root,
+	);
+	ir.pushed = program.pushed;
+	ir.dead = program.dead;
+	ir
+}
+
+// the type of a point follows from the types of its operands
+fn typ(
+	code: &[Point],
+	types: &[Type],
+	registers: &BTreeMap<IDX, Type>,
+	point: &Point,
+) -> Type {
+	// the points of a graph are all of a type, an empty one is Integer
+	let element = |idx: IDX| match &code[idx] {
+		Point::Graph(points) if !points.is_empty() => types[points[0]],
+		_ => Type::Integer,
+	};
+	match point {
+		Point::Integer(_) | Point::Tally(_) => Type::Integer,
+		Point::Decimal(_) => Type::Decimal,
+		Point::Input(Name::F32 | Name::F64 | Name::F128) => Type::Decimal,
+		Point::Input(_) => Type::Integer,
+		Point::Graph(points) => Type::Graph(points.len()),
+		Point::Op2(
+			Name::Eq
+			| Name::Ne
+			| Name::Gt
+			| Name::Lt
+			| Name::Ge
+			| Name::Le,
+			_,
+			_,
+		) => Type::Integer,
+		Point::Op2(_, a, b) => match (types[*a], types[*b]) {
+			(Type::Decimal, _) | (_, Type::Decimal) => Type::Decimal,
+			_ => Type::Integer,
+		},
+		Point::Op1(Name::Div, _) => Type::Decimal,
+		Point::Op1(Name::Not, _) => Type::Integer,
+		Point::Op1(_, a) => match types[*a] {
+			Type::Decimal => Type::Decimal,
+			_ => Type::Integer,
+		},
+		Point::If(_, a, _) => types[*a],
+		Point::Index(a, _) => match code[*a] {
+			Point::Filter(a, _) => element(a),
+			_ => element(*a),
+		},
+		Point::Filter(a, _) => match types[*a] {
+			Type::Graph(n) => Type::Array(n),
+			_ => Type::Array(0),
+		},
+		Point::Sequence(_) => Type::Sequence,
+		Point::Clock(_) => Type::Integer,
+		Point::Register => match registers.get(&types.len()) {
+			Some(Type::Decimal) => Type::Decimal,
+			_ => Type::Integer,
+		},
+		Point::Latch(_, _, v) => types[*v],
+		Point::Error(_) | Point::Dummy => Type::Error,
+	}
+}
+
+pub fn print(ir: &Ir) -> String {
+	let mut output = format!(
+		"rplir {}\nreduced {} points to {}, {} joined, {} dead\n\n",
+		VERSION,
+		ir.pushed,
+		ir.code.len(),
+		ir.pushed.saturating_sub(ir.code.len() + ir.dead),
+		ir.dead
+	);
+	for (idx, point) in ir.code.iter().enumerate() {
+		output.push_str(&format!("%{} {}", idx, type_word(ir.types[idx])));
+		if let Some(slot) = ir.slots[idx] {
+			output.push_str(&format!(" @{}", slot));
+		}
+		output.push_str(&format!(" = {}\n", op(point)));
+	}
+	output.push('\n');
+	for (idx, (name, a, b)) in &ir.each {
+		output.push_str(&format!(
+			"loop %{} = {} %{} %{}\n",
+			idx,
+			word(*name),
+			a,
+			b
+		));
+	}
+	for (path, idx) in &ir.labels {
+		output.push_str(&format!("label {} = %{}\n", path, idx));
+	}
+	output.push_str(&format!("root = %{}\n", ir.root));
+	output
+}
+
+fn op(point: &Point) -> String {
+	let refs = |idxs: &[IDX]| {
+		idxs.iter()
+			.map(|idx| format!(" %{}", idx))
+			.collect::<String>()
+	};
+	match point {
+		Point::Integer(v) => format!("int {}", v),
+		Point::Decimal(v) => format!("dec {:?}", v),
+		Point::Input(size) => format!("input {}", word(*size)),
+		Point::Op1(name, a) => {
+			format!("op1 {}{}", word(*name), refs(&[*a]))
+		}
+		Point::Op2(name, a, b) => {
+			format!("op2 {}{}", word(*name), refs(&[*a, *b]))
+		}
+		Point::Sequence(n) => format!("seq {}", n),
+		Point::Clock(period) => format!("clock {}", period),
+		Point::Register => "reg".to_string(),
+		Point::Error(e) => format!("error {:?}", e),
+		Point::Dummy => "dummy".to_string(),
+		_ => {
+			let name = match point {
+				Point::Graph(_) => "graph",
+				Point::If(_, _, _) => "if",
+				Point::Index(_, _) => "index",
+				Point::Filter(_, _) => "filter",
+				Point::Latch(_, _, _) => "latch",
+				_ => "tally",
+			};
+			format!("{}{}", name, refs(&operands(point)))
+		}
+	}
+}
+
+fn type_word(typ: Type) -> String {
+	match typ {
+		Type::Integer => "i64".to_string(),
+		Type::Decimal => "f64".to_string(),
+		Type::Graph(n) => format!("graph[{}]", n),
+		Type::Array(n) => format!("array[{}]", n),
+		Type::Sequence => "seq".to_string(),
+		Type::Error => "error".to_string(),
+	}
+}
+
+fn word(name: Name) -> String {
+	format!("{:?}", name).to_lowercase()
+}
+
+fn named(word: &str, names: &[Name]) -> Option<Name> {
+	names
+		.iter()
+		.find(|name| format!("{:?}", name).to_lowercase() == word)
+		.copied()
+}
+
+// a .rplir file back into the points it was printed from, every type and
+// slot written has to be the one the points give, and every operand and
+// loop of the kind its point takes
+pub fn parse(text: &str) -> Result<Ir, String> {
+	let mut code = Vec::new();
+	let mut written = Vec::new(); // the type and slot of every point
+	let mut labels = BTreeMap::new();
+	let mut each = BTreeMap::new();
+	let mut root = None;
+	let mut counts = None;
+
+	let mut lines = text
+		.lines()
+		.enumerate()
+		.map(|(i, line)| (i + 1, line.trim()))
+		.filter(|(_, line)| !line.is_empty() && !line.starts_with("//"));
+	match lines.next() {
+		Some((_, line)) if line == format!("rplir {}", VERSION) => {}
+		Some((n, line)) => {
+			return Err(format!(
+				"rplir line {}: {} is not a header, a .rplir file starts with rplir {}",
+				n, line, VERSION
+			))
+		}
+		None => return Err("rplir: the file is empty".to_string()),
+	}
+	for (n, line) in lines {
+		let at = |e: String| format!("rplir line {}: {}", n, e);
+		let words: Vec<&str> = line.split_whitespace().collect();
+		match words[0] {
+			"reduced" => counts = Some(reduced(&words).map_err(at)?),
+			"loop" => {
+				let (idx, rest) = assigned(&words).map_err(at)?;
+				match rest {
+					[name, a, b] => {
+						let name = named(name, &OPS).ok_or_else(|| {
+							at(format!("{} is not an operator", name))
+						})?;
+						each.insert(
+							idx,
+							(
+								name,
+								index(a).map_err(at)?,
+								index(b).map_err(at)?,
+							),
+						);
+					}
+					_ => {
+						return Err(at(format!(
+							"{} is not loop %n = op %a %b",
+							line
+						)))
+					}
+				}
+			}
+			"label" => match words[..] {
+				[_, path, "=", idx] => {
+					labels
+						.insert(path.to_string(), index(idx).map_err(at)?);
+				}
+				_ => {
+					return Err(at(format!(
+						"{} is not label path = %n",
+						line
+					)))
+				}
+			},
+			"root" => match words[..] {
+				[_, "=", idx] => root = Some(index(idx).map_err(at)?),
+				_ => return Err(at(format!("{} is not root = %n", line))),
+			},
+			word if word.starts_with('%') => {
+				let idx = index(word).map_err(at)?;
+				if idx != code.len() {
+					return Err(at(format!(
+						"%{} is out of order, the next point is %{}",
+						idx,
+						code.len()
+					)));
+				}
+				let (head, body) =
+					line.split_once(" = ").ok_or_else(|| {
+						at(format!("{} is not %n type = op", line))
+					})?;
+				let head: Vec<&str> = head.split_whitespace().collect();
+				let (typ, slot) = match head[1..] {
+					[typ] => (typ, None),
+					[typ, slot] => match slot.strip_prefix('@') {
+						Some(slot) => (
+							typ,
+							Some(slot.parse::<usize>().map_err(|_| {
+								at(format!("@{} is not a slot", slot))
+							})?),
+						),
+						None => {
+							return Err(at(format!(
+								"{} is not a slot",
+								slot
+							)))
+						}
+					},
+					_ => {
+						return Err(at(format!(
+							"{} is not %n type @slot",
+							head.join(" ")
+						)))
+					}
+				};
+				let point = point(body, idx).map_err(at)?;
+				written.push((n, typ.to_string(), slot));
+				code.push(point);
+			}
+			word => {
+				return Err(at(format!(
+					"{} is not a point, loop, label or root",
+					word
+				)))
+			}
+		}
+	}
+
+	let len = code.len();
+	let bound = |idx: IDX, what: &str| match idx < len {
+		true => Ok(idx),
+		false => Err(format!(
+			"rplir: {} %{} is past the last point %{}",
+			what,
+			idx,
+			len.saturating_sub(1)
+		)),
+	};
+	let root = bound(root.ok_or("rplir: there's no root = %n")?, "root")?;
+	for idx in labels.values() {
+		bound(*idx, "label")?;
+	}
+	for (idx, (_, a, b)) in &each {
+		bound(*idx, "loop")?;
+		bound(*a, "loop")?;
+		bound(*b, "loop")?;
+	}
+
+	let mut ir = Ir::new(code, labels, each, root);
+	for (idx, (n, typ, slot)) in written.into_iter().enumerate() {
+		if typ != type_word(ir.types[idx]) {
+			return Err(format!(
+				"rplir line {}: %{} is {}, its operands make it {}",
+				n,
+				idx,
+				typ,
+				type_word(ir.types[idx])
+			));
+		}
+		if slot != ir.slots[idx] {
+			return Err(format!(
+				"rplir line {}: %{} has slot {:?}, points are given slots in order, so it's {:?}",
+				n, idx, slot, ir.slots[idx]
+			));
+		}
+		fits(&ir, idx).map_err(|e| format!("rplir line {}: {}", n, e))?;
+	}
+	for (idx, (_, a, b)) in &ir.each {
+		let n = match ir.types[*idx] {
+			Type::Graph(n) => n,
+			x => {
+				return Err(format!(
+					"rplir: loop %{} is {}, a loop computes a graph",
+					idx,
+					type_word(x)
+				))
+			}
+		};
+		for operand in [a, b] {
+			match ir.types[*operand] {
+				Type::Integer | Type::Decimal => {}
+				Type::Graph(m) if m == n => {}
+				x => {
+					return Err(format!(
+						"rplir: loop %{} over %{}, a {} isn't a single value or a graph[{}] like %{}",
+						idx,
+						operand,
+						type_word(x),
+						n,
+						idx
+					))
+				}
+			}
+		}
+	}
+	if let Some((pushed, dead)) = counts {
+		ir.pushed = pushed;
+		ir.dead = dead;
+	}
+	Ok(ir)
+}
+
+// the operands of a point are of the kinds its operator takes, a graph
+// is counted and indexed, single values are computed with
+fn fits(ir: &Ir, idx: IDX) -> Result<(), String> {
+	let kind = |operand: IDX, ok: bool, what: &str| match ok {
+		true => Ok(()),
+		false => Err(format!(
+			"%{} needs %{} to be {}, not {}",
+			idx,
+			operand,
+			what,
+			type_word(ir.types[operand])
+		)),
+	};
+	let single = |operand: IDX| {
+		kind(
+			operand,
+			matches!(ir.types[operand], Type::Integer | Type::Decimal),
+			"a single value",
+		)
+	};
+	let array = |operand: IDX| {
+		kind(
+			operand,
+			matches!(ir.types[operand], Type::Graph(_) | Type::Array(_)),
+			"a graph or a filtered array",
+		)
+	};
+	match ir.code[idx] {
+		Point::Op1(_, a) => single(a),
+		Point::Op2(_, a, b) => single(a).and(single(b)),
+		// a match that runs out of arms is an if whose other branch is an
+		// error, it's raised if it's taken
+		Point::If(c, a, b) => {
+			single(c)?;
+			single(a)?;
+			kind(
+				b,
+				ir.types[a] == ir.types[b] || ir.types[b] == Type::Error,
+				&type_word(ir.types[a]),
+			)
+		}
+		Point::Index(a, i) => {
+			array(a)?;
+			kind(i, ir.types[i] == Type::Integer, "an Integer")
+		}
+		Point::Tally(a) => array(a),
+		Point::Filter(a, m) => {
+			kind(a, matches!(ir.types[a], Type::Graph(_)), "a graph")?;
+			kind(m, ir.types[m] == ir.types[a], &type_word(ir.types[a]))
+		}
+		// a register is written with values of its own type
+		Point::Latch(r, s, v) => {
+			kind(r, matches!(ir.code[r], Point::Register), "a register")?;
+			single(s)?;
+			single(v)?;
+			kind(v, ir.types[v] == ir.types[r], &type_word(ir.types[r]))
+		}
+		_ => Ok(()),
+	}
+}
+
+// reduced 27 points to 14, 11 joined, 2 dead
+fn reduced(words: &[&str]) -> Result<(usize, usize), String> {
+	match words {
+		["reduced", pushed, "points", "to", _, _, "joined,", dead, "dead"] => {
+			match (pushed.parse(), dead.parse()) {
+				(Ok(pushed), Ok(dead)) => Ok((pushed, dead)),
+				_ => Err(format!(
+					"{} is not a count of points",
+					words.join(" ")
+				)),
+			}
+		}
+		_ => Err(format!(
+			"{} is not reduced n points to n, n joined, n dead",
+			words.join(" ")
+		)),
+	}
+}
+
+// loop %3 = add %1 %2 -> 3, [add, %1, %2]
+fn assigned<'a, 'b>(
+	words: &'a [&'b str],
+) -> Result<(IDX, &'a [&'b str]), String> {
+	match words {
+		[_, idx, "=", rest @ ..] => Ok((index(idx)?, rest)),
+		_ => {
+			Err(format!("{} is not {} %n = ..", words.join(" "), words[0]))
+		}
+	}
+}
+
+fn index(word: &str) -> Result<IDX, String> {
+	word.strip_prefix('%')
+		.and_then(|idx| idx.parse::<IDX>().ok())
+		.ok_or_else(|| format!("{} is not a point, points are %n", word))
+}
+
+// the operator of point idx, its operands come before it
+fn point(body: &str, idx: IDX) -> Result<Point, String> {
+	let (op, rest) = body.split_once(' ').unwrap_or((body, ""));
+	let args: Vec<&str> = rest.split_whitespace().collect();
+	let refs = |args: &[&str]| -> Result<Vec<IDX>, String> {
+		let mut refs = Vec::new();
+		for arg in args {
+			let operand = index(arg)?;
+			if operand >= idx {
+				return Err(format!(
+					"%{} needs %{}, a point only needs the points before it",
+					idx, operand
+				));
+			}
+			refs.push(operand);
+		}
+		Ok(refs)
+	};
+	let operator = |word: &str| {
+		named(word, &OPS)
+			.ok_or_else(|| format!("{} is not an operator", word))
+	};
+	let number =
+		|word: &str, what: &str| format!("{} is not {}", word, what);
+	Ok(match (op, &args[..]) {
+		("int", [v]) => {
+			Point::Integer(v.parse().map_err(|_| number(v, "an Integer"))?)
+		}
+		("dec", [v]) => {
+			Point::Decimal(v.parse().map_err(|_| number(v, "a Decimal"))?)
+		}
+		("seq", [n]) => Point::Sequence(
+			n.parse().map_err(|_| number(n, "an Integer"))?,
+		),
+		("input", [size]) => Point::Input(
+			named(size, &SIZES).ok_or_else(|| number(size, "a size"))?,
+		),
+		("error", _) => Point::Error(unquote(rest)?),
+		("dummy", []) => Point::Dummy,
+		("op1", [name, a]) => Point::Op1(operator(name)?, refs(&[*a])?[0]),
+		("op2", [name, a, b]) => {
+			let r = refs(&[*a, *b])?;
+			Point::Op2(operator(name)?, r[0], r[1])
+		}
+		("graph", _) => Point::Graph(refs(&args)?),
+		("if", [_, _, _]) => {
+			let r = refs(&args)?;
+			Point::If(r[0], r[1], r[2])
+		}
+		("index", [_, _]) => {
+			let r = refs(&args)?;
+			Point::Index(r[0], r[1])
+		}
+		("filter", [_, _]) => {
+			let r = refs(&args)?;
+			Point::Filter(r[0], r[1])
+		}
+		("tally", [_]) => Point::Tally(refs(&args)?[0]),
+		("clock", [period]) => match tokenizer(period).as_deref() {
+			Ok([t]) if t.of.kind == Kind::Clock => {
+				Point::Clock(period.to_string())
+			}
+			_ => return Err(number(period, "a period, like 1s or 60ms")),
+		},
+		("reg", []) => Point::Register,
+		("latch", [_, _, _]) => {
+			let r = refs(&args)?;
+			Point::Latch(r[0], r[1], r[2])
+		}
+		_ => {
+			return Err(format!(
+				"{} is not an operator and its operands",
+				body
+			))
+		}
+	})
+}
+
+// "a \"quoted\" string" as it's printed, escapes and all
+fn unquote(text: &str) -> Result<String, String> {
+	let inner = text
+		.strip_prefix('"')
+		.and_then(|text| text.strip_suffix('"'))
+		.ok_or_else(|| format!("{} is not a quoted string", text))?;
+	let mut string = String::new();
+	let mut chars = inner.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			string.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('n') => string.push('\n'),
+			Some('t') => string.push('\t'),
+			Some('r') => string.push('\r'),
+			Some('0') => string.push('\0'),
+			Some('u') => {
+				let code: String = chars
+					.by_ref()
+					.skip(1)
+					.take_while(|c| *c != '}')
+					.collect();
+				let c = u32::from_str_radix(&code, 16)
+					.ok()
+					.and_then(char::from_u32)
+					.ok_or_else(|| {
+						format!("\\u{{{}}} is not a char", code)
+					})?;
+				string.push(c);
+			}
+			Some(c) => string.push(c),
+			None => return Err(format!("{} ends in an escape", text)),
+		}
+	}
+	Ok(string)
+}
@@ -1,5 +1,13 @@
+pub mod dot;
+pub mod eliminator;
 pub mod expander;
+pub mod ir;
 pub mod parser;
 pub mod reducer;
+pub mod runtime;
 pub mod tokenizer;
-// pub mod typer;
+pub mod transpiler;
+pub mod typer;
+
+#[cfg(test)]
+mod tests;
@@ -1,5 +1,5 @@
 // tokens -> ast-vector -> btree-graph
-/**
+/*
  * TODO:
  * --	change AST to an array, and make nested references indexes into this array
  * 	this negates the need for boxed values, which are a mess
@@ -9,6 +9,7 @@
  */
 use super::tokenizer::{Kind, Name, Token};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 pub type EID = usize;
 
@@ -17,7 +18,6 @@ pub type IDX = usize; // ast node position in vector
 #[derive(Debug, Clone)]
 pub enum Ast {
 	Nothing,
-	Parent(EID),
 	//
 	Integer(String),
 	Decimal(String),
@@ -30,6 +30,9 @@ pub enum Ast {
 
 	Apply(Box<Ast>, Box<Ast>), //	graph { .. }
 
+	If(Box<Ast>, Box<Ast>, Box<Ast>), // if (a) b else c
+	Range(Name, Box<Ast>, Name, Box<Ast>), // a < i <= b, a or b may be Nothing
+
 	Key(String, Box<Ast>), // word: exp
 	// Ret(Box<Ast>),                 // -> exp
 	Ref(EID, String),              // word
@@ -55,6 +58,49 @@ pub fn parser(tokens: &Vec<Token>) -> Rast {
 	cursor.program()
 }
 
+// the line each labelled point is defined on, by its path, root.q.a. a
+// graph is only named by a label when it's the label's value, a: {..} or
+// a: Base {..}, the points of other graphs have no path here
+pub fn spans(tokens: &[Token]) -> BTreeMap<String, u32> {
+	let mut spans = BTreeMap::new();
+	let mut scopes: Vec<Option<String>> = Vec::new();
+	let mut label = None;
+	for (i, token) in tokens.iter().enumerate() {
+		match token.of.name {
+			Name::Key | Name::Las => {
+				let key = &token.meta.text;
+				let key = key[..key.len() - 1].to_string();
+				let path: Option<Vec<String>> =
+					scopes.iter().cloned().collect();
+				if let Some(mut path) = path {
+					path.insert(0, "root".to_string());
+					path.push(key.clone());
+					spans.entry(path.join(".")).or_insert(token.meta.line);
+				}
+				label = Some(key);
+				continue;
+			}
+			Name::BracketLF => scopes.push(label.take()),
+			Name::BracketRT => {
+				scopes.pop();
+			}
+			// a: Base {..}, the applied graph is a
+			Name::Ref
+				if label.is_some()
+					&& matches!(
+						tokens.get(i + 1),
+						Some(t) if t.of.name == Name::BracketLF
+					) =>
+			{
+				continue
+			}
+			_ => {}
+		}
+		label = None;
+	}
+	spans
+}
+
 fn strip_keys(ast: &Ast) -> Ast {
 	match ast {
 		Ast::Key(_, point) => *point.clone(),
@@ -66,9 +112,9 @@ fn flatten_key(top: bool, point: &Ast, flat_points: &mut Vec<Ast>) {
 	match point {
 		Ast::Key(label, point) => {
 			flat_points
-				.push(Ast::Key(label.clone(), Box::new(strip_keys(&point))));
+				.push(Ast::Key(label.clone(), Box::new(strip_keys(point))));
 
-			flatten_key(false, &point, flat_points);
+			flatten_key(false, point, flat_points);
 		}
 		Ast::Ref(_, label) => {
 			if top {
@@ -90,11 +136,6 @@ fn flatten_key(top: bool, point: &Ast, flat_points: &mut Vec<Ast>) {
 		}
 	}
 }
-fn flatten_keys(points: Vec<Ast>, flat_points: &mut Vec<Ast>) {
-	for point in points {
-		flatten_key(true, &point, flat_points)
-	}
-}
 
 impl Tokens<'_> {
 	fn program(&self) -> Rast {
@@ -102,29 +143,39 @@ impl Tokens<'_> {
 	}
 
 	fn points(&self, op2: bool, stops: &[Name]) -> Rasts {
-		let mut points: Vec<Ast> = vec![];
+		let mut flat_points = vec![];
 		self.clear_stops();
 		while self.until(0, stops) {
-			if op2 {
-				points.push(self.return_exp()?);
+			// (jeff) is the unnamed point jeff refers to, jeff is jeff: jeff
+			let paren = self.is(0, Name::ParenLF);
+			let point = if op2 {
+				self.return_exp()?
+			} else if self.any(0, &[Name::Key, Name::Las, Name::Arrow])
+				|| self.dotted(0)
+			{
+				self.point_exp()?
+			} else if self.is(0, Name::Add)
+				&& (self.any(1, &[Name::Key, Name::Las]) || self.dotted(1))
+			{
+				// +z: 1 adds a point to the graph it's applied to
+				self.eat(Name::Add)?;
+				Ast::Op1(Name::Add, Box::new(self.point_exp()?))
 			} else {
-				if self.any(0, &[Name::Key, Name::Las, Name::Arrow]) {
-					points.push(self.point_exp()?);
-				} else {
-					points.push(self.pattern_exp()?);
-				}
+				self.pattern_exp()?
+			};
+			match point {
+				Ast::Ref(_, _) if paren => flat_points.push(point),
+				_ => flatten_key(true, &point, &mut flat_points),
 			}
 			self.clear_stops();
 		}
 
-		let mut flat_points = vec![];
-		flatten_keys(points, &mut flat_points);
-
 		Ok(flat_points)
 	}
 
+	// a guarded arm of a match graph, guard -> value
 	fn return_exp(&self) -> Rast {
-		let mut left = self.apply()?;
+		let mut left = self.or_exp()?;
 		if self.is(0, Name::Arrow) {
 			self.eat(Name::Arrow)?;
 			left = Ast::Op2(
@@ -138,6 +189,20 @@ impl Tokens<'_> {
 	}
 
 	fn point_exp(&self) -> Rast {
+		// net.port: 80 is net: {port: 80}
+		if self.dotted(0) {
+			let mut labels = vec![self.eat(Name::Ref)?.meta.text.clone()];
+			while self.is(0, Name::Select) && self.is(1, Name::Ref) {
+				self.eat(Name::Select)?;
+				labels.push(self.eat(Name::Ref)?.meta.text.clone());
+			}
+			self.eat(Name::Select)?;
+			let mut point = self.point_exp()?;
+			for label in labels.into_iter().rev() {
+				point = Ast::Key(label, Box::new(Ast::Graph(0, vec![point])));
+			}
+			return Ok(point);
+		}
 		if self.is(0, Name::Las) {
 			let key = &self.eat(Name::Las)?.meta.text;
 			let label = key[..key.len() - 1].to_string().clone();
@@ -169,7 +234,26 @@ impl Tokens<'_> {
 			left = Ast::Op2(
 				Name::Pattern,
 				Box::new(left),
-				Box::new(self.sizer_exp()?),
+				Box::new(self.target_exp()?),
+			);
+		}
+
+		Ok(left)
+	}
+
+	// the target of a pattern may be labelled, and sized on either side
+	// 	Point {x, y} ~ [Point]! my_point: Point {x: 10, y: 20}
+	fn target_exp(&self) -> Rast {
+		if self.is(0, Name::Key) {
+			return self.point_exp();
+		}
+		let mut left = self.signal_exp()?;
+		if self.is(0, Name::Sizer) {
+			self.eat(Name::Sizer)?;
+			left = Ast::Op2(
+				Name::Sizer,
+				Box::new(left),
+				Box::new(self.target_exp()?),
 			);
 		}
 
@@ -219,12 +303,27 @@ impl Tokens<'_> {
 	}
 
 	fn shape_exp(&self) -> Rast {
-		let mut left = self.or_exp()?;
+		let mut left = self.filter_exp()?;
 		while self.is(0, Name::Shape) {
 			self.eat(Name::Shape)?;
 			left = Ast::Op2(
 				Name::Shape,
 				Box::new(left),
+				Box::new(self.filter_exp()?),
+			);
+		}
+
+		Ok(left)
+	}
+
+	// array ` mask, the mask is usually a comparison, a ` a > 2
+	fn filter_exp(&self) -> Rast {
+		let mut left = self.or_exp()?;
+		while self.is(0, Name::Not) {
+			self.eat(Name::Not)?;
+			left = Ast::Op2(
+				Name::Not,
+				Box::new(left),
 				Box::new(self.or_exp()?),
 			);
 		}
@@ -336,33 +435,45 @@ impl Tokens<'_> {
 		}
 	}
 
+	// lib.Base {..} applies to the selected graph
 	fn select_exp(&self) -> Rast {
 		let mut left = self.apply()?;
 		while self.any(0, &[Name::Select, Name::Index]) {
-			let operator = self.eats(&[Name::Select, Name::Index])?;
+			let mut operator =
+				self.eats(&[Name::Select, Name::Index])?.of.name;
+			// base.(i + 1) selects by a computed index, base#(i + 1)
+			if self.is(0, Name::ParenLF) {
+				operator = Name::Index;
+			}
 			left = Ast::Op2(
-				operator.of.name,
+				operator,
 				Box::new(left),
-				Box::new(self.apply()?),
+				Box::new(self.primary()?),
 			);
+			while self.any(0, &[Name::BracketLF, Name::ParenLF]) {
+				left = Ast::Apply(Box::new(left), Box::new(self.primary()?));
+			}
 		}
 
 		Ok(left)
 	}
 
+	// Base {..} {..} applies the second graph to the first applied
 	fn apply(&self) -> Rast {
 		let mut left = self.primary()?;
-		if self.any(0, &[Name::BracketLF, Name::ParenLF]) {
+		while self.any(0, &[Name::BracketLF, Name::ParenLF]) {
 			left = Ast::Apply(Box::new(left), Box::new(self.primary()?));
 		}
 
 		Ok(left)
 	}
 	fn primary(&self) -> Rast {
-		if self.is(0, Name::Ref) {
+		if self.word(0, "if") {
+			self.if_exp()
+		} else if self.is(0, Name::Ref) {
 			self.ref_()
 		} else if self.of(0, Kind::Bracket) {
-			self.graph_exp(false)
+			self.graph_exp(self.is_match())
 		} else if self.of(0, Kind::Paren) {
 			self.paren_exp()
 		} else if self.of(0, Kind::Squaren) {
@@ -379,6 +490,18 @@ impl Tokens<'_> {
 		Ok(Ast::Graph(0, points))
 	}
 
+	// the condition is always parenthesized, so a graph following it is
+	// the branch and not applied to it
+	fn if_exp(&self) -> Rast {
+		self.eat_word("if")?;
+		let cond = self.paren_exp()?;
+		let then = self.or_exp()?;
+		self.clear_newlines();
+		self.eat_word("else")?;
+		let other = self.or_exp()?;
+		Ok(Ast::If(Box::new(cond), Box::new(then), Box::new(other)))
+	}
+
 	fn paren_exp(&self) -> Rast {
 		self.eat(Name::ParenLF)?;
 		let exp = self.or_exp();
@@ -508,12 +631,78 @@ impl Tokens<'_> {
 		}
 	}
 
+	// a graph is a match when its first point is an arm, guard -> value
+	// { x < 0 -> 0 - x, x -> x }
+	fn is_match(&self) -> bool {
+		if self.any(1, &[Name::Key, Name::Las, Name::Arrow]) {
+			return false;
+		}
+		let mut depth = 0;
+		let mut offset = 1;
+		while let Some(t) = self.get(offset) {
+			match t.of.name {
+				Name::ParenLF | Name::SquarenLF | Name::BracketLF => {
+					depth += 1
+				}
+				Name::ParenRT | Name::SquarenRT | Name::BracketRT => {
+					if depth == 0 {
+						return false;
+					}
+					depth -= 1
+				}
+				Name::Arrow if depth == 0 => return true,
+				_ if depth == 0 && t.of.kind == Kind::Stop => return false,
+				_ => {}
+			}
+			offset += 1;
+		}
+		false
+	}
+
+	fn eat_word(&self, word: &str) -> Result<&Token, String> {
+		let t = self.eat(Name::Reserved)?;
+		if t.meta.text == word {
+			Ok(t)
+		} else {
+			Err(format!(
+				"UnexpectedToken: {:?} of {:?}:{:?} on line {}\nExpected word: {:?}",
+				t.meta.text, t.of.kind, t.of.name, t.meta.line, word
+			))
+		}
+	}
+
+	fn word(&self, offset: usize, word: &str) -> bool {
+		match self.get(offset) {
+			Some(t) => t.of.name == Name::Reserved && t.meta.text == word,
+			None => false,
+		}
+	}
+
+	// newlines before an else still belong to the if
+	fn clear_newlines(&self) {
+		if self.is(0, Name::Newline) && self.word(1, "else") {
+			*self.cursor.borrow_mut() += 1;
+		}
+	}
+
 	fn clear_stops(&self) {
 		while self.of(0, Kind::Stop) {
 			*self.cursor.borrow_mut() += 1;
 		}
 	}
 
+	// a.b.c: is a key of nested graphs
+	fn dotted(&self, offset: usize) -> bool {
+		if !self.is(offset, Name::Ref) {
+			return false;
+		}
+		let mut i = offset + 1;
+		while self.is(i, Name::Select) && self.is(i + 1, Name::Ref) {
+			i += 2;
+		}
+		self.is(i, Name::Select) && self.is(i + 1, Name::Key)
+	}
+
 	fn get(&self, offset: usize) -> Option<&Token> {
 		if *self.cursor.borrow() + offset < self.tokens.len() {
 			Some(&self.tokens[*self.cursor.borrow() + offset])
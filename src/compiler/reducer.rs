@@ -20,15 +20,23 @@ array: [100 i32]! {1, 2, 3}
 
 */
 
-use super::expander::Maps;
-use super::parser::{Ast, IDX};
+use super::expander::{find, is_arm, Maps};
+use super::parser::{Ast, EID, IDX};
 use super::tokenizer::Name;
-use std::collections::BTreeMap;
+use super::typer::{size, unbounded, Typ};
+use std::collections::{BTreeMap, BTreeSet};
 
-pub struct Program {
-	data: Vec<i32>,
-	code: Vec<Point>,
-	push: Vec<i32>,
+pub struct Program<'a> {
+	pub envs: &'a Maps,
+	pub code: Vec<Point>,
+	pub points: BTreeMap<(EID, String), IDX>, // labelled points
+	pending: BTreeSet<(EID, String)>,         // labelled points being reduced
+	pub each: BTreeMap<IDX, (Name, IDX, IDX)>, // elementwise graphs
+	pub warnings: Vec<String>,
+	pub root: IDX,
+	interned: BTreeMap<String, IDX>, // points by their form, f64 has no Eq
+	pub pushed: usize, // points reduced, before identical ones are joined
+	pub dead: usize,   // points removed, nothing kept needs them
 }
 
 #[derive(Debug, Clone)]
@@ -37,74 +45,1176 @@ pub enum Point {
 	Error(String),
 	Graph(Vec<IDX>),
 	Integer(i64),
-	Add(IDX, IDX, IDX),
+	Decimal(f64),
+	Input(Name), // [i32], written from outside the program
+	Op1(Name, IDX),
+	Op2(Name, IDX, IDX),
+	If(IDX, IDX, IDX), // only the taken branch is evaluated
+	Index(IDX, IDX),   // array#i, when i isn't known until runtime
+	Sequence(i64),     // n, n + 1, .. only ever sized or indexed, never whole
+	Filter(IDX, IDX),  // array ` mask, as long as the mask has 1s
+	Tally(IDX),        // #array, when its length isn't known until runtime
+	Clock(String),     // 1s, fired from outside, its value is its phase
+	Register,          // the value last latched into it, 0 until then
+	Latch(IDX, IDX, IDX), // register, signal, value, written when it fires
 }
 
-pub fn reducer(ast: &Ast, envs: &Maps) -> Result<IDX, String> {
+pub fn reducer<'a>(
+	ast: &Ast,
+	envs: &'a Maps,
+) -> Result<Program<'a>, String> {
 	let mut parse = Program {
-		// don't actually need code AND data, because in this system they are the same thing
-		data: Vec::new(),
+		// don't need code AND data, because in this system they are the same thing
+		envs,
 		code: Vec::new(),
-		push: Vec::new(),
+		points: BTreeMap::new(),
+		pending: BTreeSet::new(),
+		each: BTreeMap::new(),
+		warnings: Vec::new(),
+		root: 0,
+		interned: BTreeMap::new(),
+		pushed: 0,
+		dead: 0,
 	};
-	let r = parse.reduce(ast, 0);
+	parse.root = parse.reduce(ast, 0)?;
 
-	dbg!(parse.code);
-
-	return r;
+	Ok(parse)
 }
 
-impl Program {
-	fn reduce(&mut self, ast: &Ast, parent: usize) -> Result<IDX, String> {
+impl Program<'_> {
+	fn reduce(&mut self, ast: &Ast, parent: EID) -> Result<IDX, String> {
 		match ast {
+			Ast::Graph(eid, points) if points.iter().any(is_arm) => {
+				self.arms(points, *eid)
+			}
+
 			Ast::Graph(eid, points) => {
 				let mut idxs = Vec::new();
+				let mut ret = None;
 				// let idx = self.code.len();
 				// self.code.push(Point::Dummy); // claim spot in array
-				for point in points {
-					idxs.push(self.reduce(point, *eid)?);
+				for (i, point) in points.iter().enumerate() {
+					let idx = match point {
+						Ast::Key(_, _) => self.reduce(point, *eid)?,
+						// an unnamed point is keyed by its index, so
+						// selecting it by position is the same point
+						_ => {
+							let key = Ast::Key(
+								i.to_string(),
+								Box::new(point.clone()),
+							);
+							self.reduce(&key, *eid)?
+						}
+					};
+					if let Ast::Key(label, _) = point {
+						if label == "<return>" {
+							ret = Some(idx);
+						}
+					}
+					idxs.push(idx);
 				}
 				// self.code[idx] = Point::Graph(idxs); // replace dummy
-				let idx = self.code.len();
-				self.code.push(Point::Graph(idxs));
+
+				// a function-like graph is only its return value
+				match ret {
+					Some(idx) => Ok(idx),
+					None => Ok(self.push(Point::Graph(idxs))),
+				}
+			}
+
+			Ast::Key(label, point) => {
+				let key = (parent, label.clone());
+				if let Some(idx) = self.points.get(&key) {
+					return Ok(*idx);
+				}
+				if !self.pending.insert(key.clone()) {
+					return Err(format!(
+						"Point {} refers to itself",
+						label
+					));
+				}
+				// a gated point is its register, what it latches may
+				// refer to it, that's the value it last latched
+				if let Ast::Op2(Name::Signal, signal, value) = &**point {
+					let register = self.push(Point::Register);
+					self.points.insert(key.clone(), register);
+					self.latch(register, signal, value, parent)?;
+					self.pending.remove(&key);
+					return Ok(register);
+				}
+				let idx = self.reduce(point, parent)?;
+				self.pending.remove(&key);
+				self.points.insert(key, idx);
 				Ok(idx)
 			}
 
-			Ast::Key(_, point) => self.reduce(point, parent),
+			Ast::Ref(eid, label) => {
+				let (eid, point) = find(self.envs, label, *eid)?;
+				match point {
+					Ast::Ref(_, _) => self.reduce(point, eid),
+					_ => {
+						let key = Ast::Key(
+							label.clone(),
+							Box::new(point.clone()),
+						);
+						self.reduce(&key, eid)
+					}
+				}
+			}
 
 			Ast::Integer(value) => {
-				let v: i64 = value.parse().unwrap();
-				let idx = self.code.len();
-				self.code.push(Point::Integer(v));
+				let v: i64 = match value.replace('\'', "").parse() {
+					Ok(v) => v,
+					Err(e) => {
+						return Err(format!(
+							"{} is not an Integer: {}",
+							value, e
+						))
+					}
+				};
+				Ok(self.push(Point::Integer(v)))
+			}
+
+			Ast::Decimal(value) => {
+				let v: f64 = match value.parse() {
+					Ok(v) => v,
+					Err(e) => {
+						return Err(format!(
+							"{} is not a Decimal: {}",
+							value, e
+						))
+					}
+				};
+				Ok(self.push(Point::Decimal(v)))
+			}
+
+			Ast::Space(sizes) => match sizes.last() {
+				Some(Ast::Size(name)) if sizes.len() == 1 => {
+					Ok(self.push(Point::Input(*name)))
+				}
+				// [3 4 i32] is an array of inputs shaped like the space
+				Some(Ast::Size(name)) => {
+					let name = *name;
+					let dims = self.dims(sizes, parent)?;
+					if dims.contains(&0) {
+						return Err(format!(
+							"{:?} has a size of 0, it holds no inputs to write",
+							sizes
+						));
+					}
+					let len = dims.iter().product::<i64>() as usize;
+					let leaves: Vec<IDX> = (0..len)
+						.map(|_| self.push(Point::Input(name)))
+						.collect();
+					Ok(self.nest(&leaves, &dims))
+				}
+				_ => Err(format!("No reduce match defined for {:?}", ast)),
+			},
+
+			// a static condition folds away, only the taken branch is reduced
+			Ast::If(cond, then, other) => {
+				let c = self.reduce(cond, parent)?;
+				match truth(&self.code[c]) {
+					Some(true) => self.reduce(then, parent),
+					Some(false) => self.reduce(other, parent),
+					None => {
+						let a = self.reduce(then, parent)?;
+						let b = self.reduce(other, parent)?;
+						self.branch(c, a, b)
+					}
+				}
+			}
+
+			// a selection is a view, the selected points are the points
+			// of the array, not copies of them
+			Ast::Op2(Name::Index, array, selector) => {
+				let a = self.reduce(array, parent)?;
+				if let Point::Sequence(start) = self.code[a] {
+					return self.nth(start, selector, parent);
+				}
+				// a filtered array is only as long as its mask says
+				if let Point::Filter(_, _) = self.code[a] {
+					if let Ast::Range(_, _, _, _) = **selector {
+						return Err(
+							"a filtered array can't be selected by a range, its length is only known at runtime"
+								.to_string(),
+						);
+					}
+					let i = self.reduce(selector, parent)?;
+					return Ok(self.push(Point::Index(a, i)));
+				}
+				let points = match &self.code[a] {
+					Point::Graph(points) => points.clone(),
+					x => return Err(format!("{:?} is not an array", x)),
+				};
+				if let Ast::Range(_, _, _, _) = **selector {
+					let mut selected = Vec::new();
+					for i in
+						self.range(selector, Some(points.len()), parent)?
+					{
+						selected.push(points[i as usize]);
+					}
+					return Ok(self.push(Point::Graph(selected)));
+				}
+				let i = self.reduce(selector, parent)?;
+				match self.code[i] {
+					Point::Integer(v)
+						if v >= 0 && (v as usize) < points.len() =>
+					{
+						Ok(points[v as usize])
+					}
+					// an index past the end wraps around, see doc/overflow,
+					// a static one is folded to the point it wraps to, so
+					// there's no point left to flag, it's a warning instead
+					Point::Integer(v) if !points.is_empty() => {
+						self.warnings.push(format!(
+							"Warning: index {} of an array of {} wraps around to {}, a static index has no overflow flag to set",
+							v,
+							points.len(),
+							wrap(v, points.len())
+						));
+						Ok(points[wrap(v, points.len())])
+					}
+					Point::Integer(v) => {
+						Err(format!("index {} of an empty array", v))
+					}
+					Point::Decimal(v) => {
+						Err(format!("index {} is not an Integer", v))
+					}
+					_ => Ok(self.push(Point::Index(a, i))),
+				}
+			}
+
+			// a bounded range on its own is the array of its indices, an
+			// unbounded one is a sequence
+			Ast::Range(lo_op, lo, _, hi)
+				if matches!(**hi, Ast::Nothing) =>
+			{
+				let start = match self.bound(lo, parent)? {
+					Some(lo) if *lo_op == Name::Lt => lo + 1,
+					Some(lo) => lo,
+					None => {
+						return Err("a range must have at least one bound"
+							.to_string())
+					}
+				};
+				Ok(self.push(Point::Sequence(start)))
+			}
+			Ast::Range(_, _, _, _) => {
+				let mut points = Vec::new();
+				for i in self.range(ast, None, parent)? {
+					points.push(self.push(Point::Integer(i)));
+				}
+				Ok(self.push(Point::Graph(points)))
+			}
+
+			Ast::Op2(Name::Signal, signal, value) => {
+				let register = self.push(Point::Register);
+				self.latch(register, signal, value, parent)?;
+				Ok(register)
+			}
+
+			Ast::Clock(period, _) => {
+				Ok(self.push(Point::Clock(period.clone())))
+			}
+
+			// the point is its value, a graph must fit in the space
+			Ast::Op2(Name::Sizer, point_a, point_b) => {
+				let (space, point) = match **point_a {
+					Ast::Space(_) => (point_a, point_b),
+					_ => (point_b, point_a),
+				};
+				let idx = self.reduce(point, parent)?;
+				// [10]! (>7) is the first 10 of the sequence
+				if let (Ast::Space(sizes), Point::Sequence(start)) =
+					(&**space, &self.code[idx])
+				{
+					let start = *start;
+					return match self.capacity(sizes, parent)? {
+						Some(n) => {
+							let points = (start..start + n as i64)
+								.map(|i| self.push(Point::Integer(i)))
+								.collect();
+							Ok(self.push(Point::Graph(points)))
+						}
+						None => Err(unbounded(&format!(
+							"{:?} of",
+							Name::Sizer
+						))),
+					};
+				}
+				if let (Ast::Space(sizes), Point::Graph(points)) =
+					(&**space, &self.code[idx])
+				{
+					let len = points.len();
+					let capacity = self.capacity(sizes, parent)?;
+					if matches!(capacity, Some(c) if len > c) {
+						return Err(format!(
+							"{} points exceed the capacity of {} of {:?}",
+							len,
+							capacity.unwrap_or(1),
+							sizes
+						));
+					}
+					// [4 f32]! {0}, the points left out are 0, like in c
+					if let Some(c) = capacity.filter(|c| len < *c) {
+						return self.fill(idx, sizes, c, parent);
+					}
+				}
 				Ok(idx)
 			}
 
+			// #x, how many points x has, known now unless x is filtered
+			Ast::Op1(Name::Index, point) => {
+				if let Some(sizes) = self.space(point) {
+					let len = match self.capacity(&sizes, parent)? {
+						Some(n) => n as i64,
+						None => 1,
+					};
+					return Ok(self.push(Point::Integer(len)));
+				}
+				let idx = self.reduce(point, parent)?;
+				match &self.code[idx] {
+					Point::Graph(points) => {
+						let len = points.len() as i64;
+						Ok(self.push(Point::Integer(len)))
+					}
+					Point::Filter(_, _) => {
+						Ok(self.push(Point::Tally(idx)))
+					}
+					Point::Sequence(_) => {
+						Err(unbounded(&format!("{:?} of", Name::Index)))
+					}
+					// a single value is one point
+					_ => Ok(self.push(Point::Integer(1))),
+				}
+			}
+
+			// `x flips every bit, +x is the magnitude, -x the negation and
+			// /x the reciprocal
+			Ast::Op1(
+				name @ (Name::Not | Name::Add | Name::Sub | Name::Div),
+				point,
+			) => {
+				let a = self.reduce(point, parent)?;
+				self.op1(*name, a)
+			}
+
+			// array ` mask
+			Ast::Op2(Name::Not, array, mask) => {
+				let a = self.reduce(array, parent)?;
+				let m = self.reduce(mask, parent)?;
+				self.filter(a, m)
+			}
+
+			// $x, the dimensions of x, {} for a single value
+			Ast::Op1(Name::Shape, point) => {
+				let dims = match &**point {
+					Ast::Space(sizes) => self.dims(sizes, parent)?,
+					_ => {
+						let idx = self.reduce(point, parent)?;
+						if let Point::Sequence(_) = self.code[idx] {
+							return Err(unbounded(&format!(
+								"{:?} of",
+								Name::Shape
+							)));
+						}
+						self.shape(idx)
+					}
+				};
+				let points = dims
+					.iter()
+					.map(|d| self.push(Point::Integer(*d)))
+					.collect();
+				Ok(self.push(Point::Graph(points)))
+			}
+
+			// x $ [10 10], the values of x in that many rows and columns
+			Ast::Op2(Name::Shape, point, dims) => {
+				let idx = self.reduce(point, parent)?;
+				if let Point::Sequence(_) = self.code[idx] {
+					return Err(unbounded(&format!(
+						"{:?} of",
+						Name::Shape
+					)));
+				}
+				let dims = match &**dims {
+					Ast::Space(sizes) => self.dims(sizes, parent)?,
+					_ => {
+						let idx = self.reduce(dims, parent)?;
+						let points = match &self.code[idx] {
+							Point::Graph(points) => points.clone(),
+							_ => vec![idx],
+						};
+						let mut dims = Vec::new();
+						for p in points {
+							match self.code[p] {
+								Point::Integer(d) if d >= 0 => {
+									dims.push(d)
+								}
+								ref x => {
+									return Err(format!(
+										"{:?} is not a dimension, dimensions are static Integers",
+										x
+									))
+								}
+							}
+						}
+						dims
+					}
+				};
+				let leaves = self.leaves(idx);
+				let count: i64 = dims.iter().product();
+				if count != leaves.len() as i64 {
+					return Err(format!(
+						"{} values can't be shaped as {:?}, that is {} values",
+						leaves.len(),
+						dims,
+						count
+					));
+				}
+				Ok(self.nest(&leaves, &dims))
+			}
+
+			// a .. b, when a or b are only graphs once reduced
+			Ast::Op2(Name::Bleed, point_a, point_b) => {
+				let a = self.reduce(point_a, parent)?;
+				let b = self.reduce(point_b, parent)?;
+				match (&self.code[a], &self.code[b]) {
+					(Point::Graph(a), Point::Graph(b)) => {
+						let points = a.iter().chain(b).cloned().collect();
+						Ok(self.push(Point::Graph(points)))
+					}
+					(x, y) => Err(format!(
+						"{:?} .. {:?} can't be joined, only graphs can",
+						x, y
+					)),
+				}
+			}
+
 			Ast::Op2(name, point_a, point_b) => {
 				let a = self.reduce(point_a, parent)?;
 				let b = self.reduce(point_b, parent)?;
-				let c_point = execute2(*name, &self.code[a], &self.code[b]);
-				let c = self.code.len();
-				self.code.push(c_point);
-				let idx = self.code.len();
-				self.code.push(Point::Add(a, b, c));
-				Ok(idx)
+				self.op2(*name, a, b)
+			}
+			x => Err(format!("No reduce match defined for {:?}", x)),
+		}
+	}
+
+	// arms become a chain of ifs, arms whose guards are static fold away,
+	// running out of arms is an error when it's run, the typer warns of it
+	fn arms(&mut self, arms: &[Ast], eid: EID) -> Result<IDX, String> {
+		let (guard, value) = match arms.first() {
+			Some(Ast::Op2(Name::Arrow, guard, value)) => (guard, value),
+			Some(x) => return Err(format!("{:?} is not an arm", x)),
+			None => {
+				return Ok(self.push(Point::Error(format!(
+					"no arm of the match in {} holds",
+					self.envs.scopes[eid].path
+				))))
+			}
+		};
+		if let Ast::Nothing = **guard {
+			return self.reduce(value, eid);
+		}
+		let c = self.reduce(guard, eid)?;
+		match truth(&self.code[c]) {
+			Some(true) => self.reduce(value, eid),
+			Some(false) => self.arms(&arms[1..], eid),
+			None => {
+				let a = self.reduce(value, eid)?;
+				let b = self.arms(&arms[1..], eid)?;
+				Ok(self.push(Point::If(c, a, b)))
 			}
-			x => return Err(format!("No reduce match defined for {:?}", x)),
 		}
 	}
+
+	// signal ? value, the value is written to the register when a clock
+	// or an input the signal is computed from fires
+	fn latch(
+		&mut self,
+		register: IDX,
+		signal: &Ast,
+		value: &Ast,
+		parent: EID,
+	) -> Result<(), String> {
+		let s = self.reduce(signal, parent)?;
+		let v = self.reduce(value, parent)?;
+		match self.code[v] {
+			Point::Graph(_) | Point::Sequence(_) | Point::Filter(_, _) => {
+				Err(format!(
+					"a signal in {} gates a graph, a register holds a single value, gate each of its points",
+					self.envs.scopes[parent].path
+				))
+			}
+			_ => {
+				self.push(Point::Latch(register, s, v));
+				Ok(())
+			}
+		}
+	}
+
+	// the points of a sequence an index selects
+	fn nth(
+		&mut self,
+		start: i64,
+		selector: &Ast,
+		parent: EID,
+	) -> Result<IDX, String> {
+		if let Ast::Range(_, _, _, _) = selector {
+			let mut points = Vec::new();
+			for i in self.range(selector, None, parent)? {
+				points.push(self.push(Point::Integer(start + i)));
+			}
+			return Ok(self.push(Point::Graph(points)));
+		}
+		let i = self.reduce(selector, parent)?;
+		match self.code[i] {
+			Point::Integer(v) if v >= 0 => {
+				Ok(self.push(Point::Integer(start + v)))
+			}
+			Point::Integer(v) => Err(format!(
+				"index {} is before the start of a sequence",
+				v
+			)),
+			_ => {
+				let start = self.push(Point::Integer(start));
+				Ok(self.push(Point::Op2(Name::Add, start, i)))
+			}
+		}
+	}
+
+	// unary operators map over graphs like binary ones
+	fn op1(&mut self, name: Name, a: IDX) -> Result<IDX, String> {
+		match &self.code[a] {
+			Point::Graph(points) => {
+				let points = points.clone();
+				let mut each = Vec::new();
+				for point in points {
+					each.push(self.op1(name, point)?);
+				}
+				return Ok(self.push(Point::Graph(each)));
+			}
+			Point::Sequence(_) => {
+				return Err(unbounded(&format!("{:?} of", name)))
+			}
+			Point::Filter(_, _) => return Err(dynamic(name)),
+			x if constant(x) => {
+				return match execute1(name, x) {
+					Point::Error(e) => Err(e),
+					point => Ok(self.push(point)),
+				}
+			}
+			_ => {}
+		}
+		let idx = self.push(Point::Op1(name, a));
+		// the bits an unsigned input doesn't have stay 0
+		match (name, &self.code[a]) {
+			(Name::Not, Point::Input(size)) => match width(*size) {
+				Some(bits) => {
+					let mask = self.push(Point::Integer((1 << bits) - 1));
+					Ok(self.push(Point::Op2(Name::And, idx, mask)))
+				}
+				None => Ok(idx),
+			},
+			_ => Ok(idx),
+		}
+	}
+
+	// the points of array where mask isn't 0, when the mask is known
+	// already they are selected now
+	fn filter(&mut self, a: IDX, m: IDX) -> Result<IDX, String> {
+		let (points, mask) = match (&self.code[a], &self.code[m]) {
+			(Point::Graph(points), Point::Graph(mask))
+				if points.len() == mask.len() =>
+			{
+				(points.clone(), mask.clone())
+			}
+			(Point::Graph(_), Point::Graph(_)) => {
+				return Err(format!(
+					"Not of shapes {:?} and {:?}, a mask is as long as its array",
+					self.shape(a),
+					self.shape(m)
+				))
+			}
+			(Point::Graph(_), x) => {
+				return Err(format!(
+					"{:?} is not a mask, masks are arrays",
+					x
+				))
+			}
+			(x, _) => {
+				return Err(format!(
+					"{:?} is not an array, only arrays are filtered",
+					x
+				))
+			}
+		};
+		let truths: Option<Vec<bool>> =
+			mask.iter().map(|m| truth(&self.code[*m])).collect();
+		match truths {
+			Some(truths) => {
+				let selected = points
+					.into_iter()
+					.zip(truths)
+					.filter(|(_, t)| *t)
+					.map(|(p, _)| p)
+					.collect();
+				Ok(self.push(Point::Graph(selected)))
+			}
+			None => Ok(self.push(Point::Filter(a, m))),
+		}
+	}
+
+	// an operator maps over graphs, a single value is used for every
+	// point of a graph, graphs must have the same shape
+	fn op2(&mut self, name: Name, a: IDX, b: IDX) -> Result<IDX, String> {
+		let len = match (&self.code[a], &self.code[b]) {
+			(Point::Sequence(_), _) | (_, Point::Sequence(_)) => {
+				return Err(unbounded(&format!("{:?} of", name)))
+			}
+			(Point::Filter(_, _), _) | (_, Point::Filter(_, _)) => {
+				return Err(dynamic(name))
+			}
+			(Point::Graph(x), Point::Graph(y)) if x.len() != y.len() => {
+				return Err(format!(
+					"{:?} of shapes {:?} and {:?}, shapes must match",
+					name,
+					self.shape(a),
+					self.shape(b)
+				))
+			}
+			(Point::Graph(x), _) | (_, Point::Graph(x)) => Some(x.len()),
+			_ => None,
+		};
+		if let Some(len) = len {
+			let mut points = Vec::new();
+			for i in 0..len {
+				let (x, y) = (self.element(a, i), self.element(b, i));
+				points.push(self.op2(name, x, y)?);
+			}
+			let idx = self.push(Point::Graph(points));
+			self.each.insert(idx, (name, a, b));
+			return Ok(idx);
+		}
+		if constant(&self.code[a]) && constant(&self.code[b]) {
+			match execute2(name, &self.code[a], &self.code[b]) {
+				Point::Error(e) => Err(e),
+				point => Ok(self.push(point)),
+			}
+		} else {
+			Ok(self.push(Point::Op2(name, a, b)))
+		}
+	}
+
+	// an if of graphs of the same length is a graph of ifs, the
+	// typer can't count ranges and filters, so lengths are checked here
+	fn branch(&mut self, c: IDX, a: IDX, b: IDX) -> Result<IDX, String> {
+		match (&self.code[a], &self.code[b]) {
+			(Point::Graph(x), Point::Graph(y)) if x.len() == y.len() => {
+				let pairs: Vec<(IDX, IDX)> =
+					x.iter().cloned().zip(y.iter().cloned()).collect();
+				let mut points = Vec::new();
+				for (x, y) in pairs {
+					points.push(self.branch(c, x, y)?);
+				}
+				Ok(self.push(Point::Graph(points)))
+			}
+			(Point::Graph(x), Point::Graph(y)) => Err(format!(
+				"TypeError: branches of if have {} and {} points, they must have as many",
+				x.len(),
+				y.len()
+			)),
+			(Point::Filter(_, _), _) | (_, Point::Filter(_, _)) => Err(
+				"TypeError: a branch of if is filtered, how many points it has isn't known until it runs".to_string(),
+			),
+			(Point::Graph(_), _) | (_, Point::Graph(_)) => Err(
+				"TypeError: a branch of if is a graph and the other isn't".to_string(),
+			),
+			_ => Ok(self.push(Point::If(c, a, b))),
+		}
+	}
+
+	fn element(&self, idx: IDX, i: usize) -> IDX {
+		match &self.code[idx] {
+			Point::Graph(points) => points[i],
+			_ => idx,
+		}
+	}
+
+	// the indices a range counts through, in order. the open end of a
+	// range stops at the end of the array, a bound that was written must
+	// fall inside it
+	fn range(
+		&mut self,
+		range: &Ast,
+		len: Option<usize>,
+		parent: EID,
+	) -> Result<Vec<i64>, String> {
+		let (lo_op, lo, hi_op, hi) = match range {
+			Ast::Range(lo_op, lo, hi_op, hi) => (*lo_op, lo, *hi_op, hi),
+			x => return Err(format!("{:?} is not a range", x)),
+		};
+		let lo = self.bound(lo, parent)?;
+		let hi = self.bound(hi, parent)?;
+		let (start, end) = match (lo_op, lo, hi_op, hi) {
+			(_, None, _, None) => {
+				return Err(
+					"a range must have at least one bound".to_string()
+				)
+			}
+			(Name::Gt | Name::Ge, None, _, _)
+			| (_, _, Name::Gt | Name::Ge, None) => {
+				return Err("a range counting down must have both bounds"
+					.to_string())
+			}
+			(Name::Gt | Name::Ge, Some(lo), _, Some(hi)) => {
+				let start = if lo_op == Name::Gt { lo - 1 } else { lo };
+				let end = if hi_op == Name::Gt { hi + 1 } else { hi };
+				if let Some(len) = len {
+					if start >= len as i64 || end < 0 {
+						return Err(format!(
+							"range {} .. {} is out of bounds of an array of {}",
+							start, end, len
+						));
+					}
+				}
+				return Ok((end..=start).rev().collect());
+			}
+			(_, lo, _, hi) => {
+				let start = match lo {
+					Some(lo) if lo_op == Name::Lt => lo + 1,
+					Some(lo) => lo,
+					None => 0,
+				};
+				let end = match (hi, len) {
+					(Some(hi), _) if hi_op == Name::Lt => hi,
+					(Some(hi), _) => hi + 1,
+					(None, Some(len)) => len as i64,
+					(None, None) => {
+						return Err(
+							"a range must have an upper bound".to_string()
+						)
+					}
+				};
+				(start, end)
+			}
+		};
+		if let Some(len) = len {
+			if start < 0 || end > len as i64 {
+				return Err(format!(
+					"range {} .. {} is out of bounds of an array of {}",
+					start,
+					end - 1,
+					len
+				));
+			}
+		}
+		Ok((start..end).collect())
+	}
+
+	fn bound(
+		&mut self,
+		bound: &Ast,
+		parent: EID,
+	) -> Result<Option<i64>, String> {
+		if let Ast::Nothing = bound {
+			return Ok(None);
+		}
+		let idx = self.reduce(bound, parent)?;
+		match self.code[idx] {
+			Point::Integer(v) => Ok(Some(v)),
+			ref x => Err(format!(
+				"{:?} is not a static Integer, the bounds of a range must be known when compiling",
+				x
+			)),
+		}
+	}
+
+	// the counts of a space, [10 10 f32] is [10, 10]
+	fn dims(
+		&mut self,
+		sizes: &[Ast],
+		parent: EID,
+	) -> Result<Vec<i64>, String> {
+		let mut dims = Vec::new();
+		for size in sizes {
+			if let Ast::Integer(_) | Ast::Ref(_, _) = size {
+				let idx = self.reduce(size, parent)?;
+				match self.code[idx] {
+					Point::Integer(n) if n >= 0 => dims.push(n),
+					Point::Graph(_) => {}
+					ref x => {
+						return Err(format!(
+							"{:?} is not a size, sizes are static Integers",
+							x
+						))
+					}
+				}
+			}
+		}
+		Ok(dims)
+	}
+
+	// a graph of graphs of the same length has the dimensions of both
+	fn shape(&self, idx: IDX) -> Vec<i64> {
+		let points = match &self.code[idx] {
+			Point::Graph(points) => points,
+			_ => return Vec::new(),
+		};
+		let mut dims = vec![points.len() as i64];
+		if let Some(first) = points.first() {
+			let inner = self.shape(*first);
+			if !inner.is_empty()
+				&& points.iter().all(|p| self.shape(*p) == inner)
+			{
+				dims.extend(inner);
+			}
+		}
+		dims
+	}
+
+	// every value of a graph of graphs, in order
+	fn leaves(&self, idx: IDX) -> Vec<IDX> {
+		match &self.code[idx] {
+			Point::Graph(points) => {
+				points.iter().flat_map(|p| self.leaves(*p)).collect()
+			}
+			_ => vec![idx],
+		}
+	}
+
+	// the values of a graph padded with 0s to the capacity of a space,
+	// in rows shaped like it, decimals in a space of decimals
+	fn fill(
+		&mut self,
+		idx: IDX,
+		sizes: &[Ast],
+		capacity: usize,
+		parent: EID,
+	) -> Result<IDX, String> {
+		let decimal = matches!(
+			sizes.last(),
+			Some(Ast::Size(name)) if size(*name) == Typ::Decimal
+		);
+		let mut leaves = Vec::new();
+		for leaf in self.leaves(idx) {
+			match self.code[leaf] {
+				Point::Integer(v) if decimal => {
+					leaves.push(self.push(Point::Decimal(v as f64)))
+				}
+				_ => leaves.push(leaf),
+			}
+		}
+		if leaves.len() > capacity {
+			return Err(format!(
+				"{} values exceed the capacity of {} of {:?}",
+				leaves.len(),
+				capacity,
+				sizes
+			));
+		}
+		while leaves.len() < capacity {
+			leaves.push(match decimal {
+				true => self.push(Point::Decimal(0.0)),
+				false => self.push(Point::Integer(0)),
+			});
+		}
+		let dims = self.dims(sizes, parent)?;
+		Ok(self.nest(&leaves, &dims))
+	}
+
+	fn nest(&mut self, leaves: &[IDX], dims: &[i64]) -> IDX {
+		match dims {
+			[] => leaves[0],
+			[_] => self.push(Point::Graph(leaves.to_vec())),
+			// a dimension of 0 has no rows, [3 0] has 3 empty ones
+			[0, ..] => self.push(Point::Graph(Vec::new())),
+			[n, rest @ ..] => {
+				let size = leaves.len() / *n as usize;
+				let rows = (0..*n as usize)
+					.map(|i| {
+						self.nest(&leaves[i * size..(i + 1) * size], rest)
+					})
+					.collect();
+				self.push(Point::Graph(rows))
+			}
+		}
+	}
+
+	// the sizes of a bare space, or of the space a label names
+	fn space(&self, ast: &Ast) -> Option<Vec<Ast>> {
+		match ast {
+			Ast::Space(sizes) => Some(sizes.clone()),
+			Ast::Ref(eid, label) => match find(self.envs, label, *eid) {
+				Ok((_, point)) => self.space(point),
+				Err(_) => None,
+			},
+			_ => None,
+		}
+	}
+
+	// how many points fit in a space, [3 4 i32] is 12. a space without
+	// a count, [i32] or [Point], holds one value of its base
+	fn capacity(
+		&mut self,
+		sizes: &[Ast],
+		parent: EID,
+	) -> Result<Option<usize>, String> {
+		let mut capacity = None;
+		for size in sizes {
+			if let Ast::Integer(_) | Ast::Ref(_, _) = size {
+				let idx = self.reduce(size, parent)?;
+				match self.code[idx] {
+					Point::Integer(n) if n >= 0 => {
+						capacity = Some(capacity.unwrap_or(1) * n as usize)
+					}
+					// [Point] is sized by a graph, it's the base
+					Point::Graph(_) => {}
+					ref x => {
+						return Err(format!(
+							"{:?} is not a size, sizes are static Integers",
+							x
+						))
+					}
+				}
+			}
+		}
+		Ok(capacity)
+	}
+
+	// identical points are one point, computed once however many graphs
+	// need it. an input is only ever itself, it's written from outside
+	fn push(&mut self, point: Point) -> IDX {
+		self.pushed += 1;
+		let key = key(&point);
+		if let Some(idx) =
+			key.as_ref().and_then(|key| self.interned.get(key))
+		{
+			return *idx;
+		}
+		let idx = self.code.len();
+		self.code.push(point);
+		if let Some(key) = key {
+			self.interned.insert(key, idx);
+		}
+		idx
+	}
+
+	// only the live points are kept, each keeps its order, so a point is
+	// still after the points it needs
+	pub fn renumber(&mut self, live: &[bool]) {
+		let mut to = vec![0; self.code.len()];
+		let mut next = 0;
+		for (idx, live) in live.iter().enumerate() {
+			if *live {
+				to[idx] = next;
+				next += 1;
+			}
+		}
+		self.dead += self.code.len() - next;
+		let code = std::mem::take(&mut self.code);
+		self.code = code
+			.into_iter()
+			.enumerate()
+			.filter(|(idx, _)| live[*idx])
+			.map(|(_, point)| renumbered(point, &to))
+			.collect();
+		self.points.retain(|_, idx| live[*idx]);
+		for idx in self.points.values_mut() {
+			*idx = to[*idx];
+		}
+		self.each = std::mem::take(&mut self.each)
+			.into_iter()
+			.filter(|(idx, _)| live[*idx])
+			.map(|(idx, (name, a, b))| (to[idx], (name, to[a], to[b])))
+			.collect();
+		self.interned = self
+			.code
+			.iter()
+			.enumerate()
+			.filter_map(|(idx, point)| Some((key(point)?, idx)))
+			.collect();
+		self.root = to[self.root];
+	}
 }
 
-fn execute2(name: Name, a: &Point, b: &Point) -> Point {
-	match name {
-		Name::Add => add(a, b),
-		_ => Point::Error(format!("fug")),
+// the points a point needs
+pub fn operands(point: &Point) -> Vec<IDX> {
+	match point {
+		Point::Graph(idxs) => idxs.clone(),
+		Point::Op2(_, a, b) => vec![*a, *b],
+		Point::If(c, a, b) => vec![*c, *a, *b],
+		Point::Index(a, i) => vec![*a, *i],
+		Point::Op1(_, a) | Point::Tally(a) => vec![*a],
+		Point::Filter(a, m) => vec![*a, *m],
+		Point::Latch(r, s, v) => vec![*r, *s, *v],
+		_ => Vec::new(),
 	}
 }
 
-fn add(a: &Point, b: &Point) -> Point {
-	use Point::{Error, Integer};
+// the clocks and inputs a signal is computed from, it fires when they do
+pub fn sources(code: &[Point], signal: IDX) -> BTreeSet<IDX> {
+	let mut sources = BTreeSet::new();
+	let mut seen = BTreeSet::new();
+	let mut stack = vec![signal];
+	while let Some(idx) = stack.pop() {
+		if !seen.insert(idx) {
+			continue;
+		}
+		match code[idx] {
+			Point::Clock(_) | Point::Input(_) => {
+				sources.insert(idx);
+			}
+			_ => stack.extend(operands(&code[idx])),
+		}
+	}
+	sources
+}
+
+// points are the same point when their forms are
+fn key(point: &Point) -> Option<String> {
+	match point {
+		Point::Input(_)
+		| Point::Dummy
+		| Point::Error(_)
+		| Point::Clock(_)
+		| Point::Register
+		| Point::Latch(_, _, _) => None,
+		_ => Some(format!("{:?}", point)),
+	}
+}
+
+fn renumbered(point: Point, to: &[IDX]) -> Point {
+	match point {
+		Point::Graph(idxs) => {
+			Point::Graph(idxs.into_iter().map(|idx| to[idx]).collect())
+		}
+		Point::Op1(name, a) => Point::Op1(name, to[a]),
+		Point::Op2(name, a, b) => Point::Op2(name, to[a], to[b]),
+		Point::If(c, a, b) => Point::If(to[c], to[a], to[b]),
+		Point::Index(a, i) => Point::Index(to[a], to[i]),
+		Point::Filter(a, m) => Point::Filter(to[a], to[m]),
+		Point::Tally(a) => Point::Tally(to[a]),
+		Point::Latch(r, s, v) => Point::Latch(to[r], to[s], to[v]),
+		point => point,
+	}
+}
+
+// where an index lands in an array of len, past either end it wraps
+pub fn wrap(i: i64, len: usize) -> usize {
+	i.rem_euclid(len as i64) as usize
+}
+
+pub fn dynamic(name: Name) -> String {
+	format!(
+		"{:?} of a filtered array, its length is only known at runtime, count it with # or index it",
+		name
+	)
+}
+
+// the bits of an unsigned size narrower than the 64 points are kept in,
+// a u64 has all of them, its values are shown unsigned, see unsigned
+fn width(size: Name) -> Option<u32> {
+	match size {
+		Name::U8 => Some(8),
+		Name::U16 => Some(16),
+		Name::U32 => Some(32),
+		_ => None,
+	}
+}
+
+// a u64 input, or its bits flipped, is kept in an i64 but it's unsigned
+pub fn unsigned(code: &[Point], idx: IDX) -> bool {
+	match code[idx] {
+		Point::Input(Name::U64) => true,
+		Point::Op1(Name::Not, a) => unsigned(code, a),
+		_ => false,
+	}
+}
+
+pub fn constant(point: &Point) -> bool {
+	matches!(point, Point::Integer(_) | Point::Decimal(_))
+}
+
+// None when the point isn't known until runtime
+pub fn truth(point: &Point) -> Option<bool> {
+	match point {
+		Point::Integer(v) => Some(*v != 0),
+		Point::Decimal(v) => Some(*v != 0.0),
+		_ => None,
+	}
+}
+
+// integers wrap on overflow, see doc/overflow
+// there are no rationals, the reciprocal of an integer is a decimal
+pub fn execute1(name: Name, a: &Point) -> Point {
+	use Point::{Decimal, Error, Integer};
+	match (name, a) {
+		(Name::Not, Integer(a)) => Integer(!a),
+		(Name::Add, Integer(a)) => Integer(a.wrapping_abs()),
+		(Name::Add, Decimal(a)) => Decimal(a.abs()),
+		(Name::Sub, Integer(a)) => Integer(a.wrapping_neg()),
+		(Name::Sub, Decimal(a)) => Decimal(-a),
+		(Name::Div, Integer(0)) => Error("/0 divides by zero".to_string()),
+		(Name::Div, Integer(a)) => Decimal(1.0 / *a as f64),
+		(Name::Div, Decimal(a)) => Decimal(1.0 / a),
+		_ => Error(format!("{:?} is not defined for {:?}", name, a)),
+	}
+}
+
+pub fn execute2(name: Name, a: &Point, b: &Point) -> Point {
+	use Point::{Decimal, Error, Integer};
 	match (a, b) {
-		(Integer(a), Integer(b)) => Integer(a + b),
-		_ => Error(format!("fug")),
+		(Integer(a), Integer(b)) => integer2(name, *a, *b),
+		(Integer(a), Decimal(b)) => decimal2(name, *a as f64, *b),
+		(Decimal(a), Integer(b)) => decimal2(name, *a, *b as f64),
+		(Decimal(a), Decimal(b)) => decimal2(name, *a, *b),
+		_ => Error(format!(
+			"{:?} is not defined for {:?} and {:?}",
+			name, a, b
+		)),
+	}
+}
+
+fn integer2(name: Name, a: i64, b: i64) -> Point {
+	use Point::{Error, Integer};
+	match name {
+		Name::Add => Integer(a.wrapping_add(b)),
+		Name::Sub => Integer(a.wrapping_sub(b)),
+		Name::Mul => Integer(a.wrapping_mul(b)),
+		Name::Div if b == 0 => Error(format!("{} / 0 divides by zero", a)),
+		Name::Div => Integer(a.wrapping_div(b)),
+		Name::Exp if b < 0 => {
+			Error(format!("{} ^ {} is not an Integer", a, b))
+		}
+		Name::Exp => Integer(a.wrapping_pow(b as u32)),
+		Name::And => Integer(a & b),
+		Name::Or => Integer(a | b),
+		Name::Eq => Integer((a == b) as i64),
+		Name::Ne => Integer((a != b) as i64),
+		Name::Gt => Integer((a > b) as i64),
+		Name::Lt => Integer((a < b) as i64),
+		Name::Ge => Integer((a >= b) as i64),
+		Name::Le => Integer((a <= b) as i64),
+		_ => Error(format!("{:?} is not defined for Integers", name)),
+	}
+}
+
+fn decimal2(name: Name, a: f64, b: f64) -> Point {
+	use Point::{Decimal, Error, Integer};
+	match name {
+		Name::Add => Decimal(a + b),
+		Name::Sub => Decimal(a - b),
+		Name::Mul => Decimal(a * b),
+		Name::Div => Decimal(a / b),
+		Name::Exp => Decimal(a.powf(b)),
+		Name::Eq => Integer((a == b) as i64),
+		Name::Ne => Integer((a != b) as i64),
+		Name::Gt => Integer((a > b) as i64),
+		Name::Lt => Integer((a < b) as i64),
+		Name::Ge => Integer((a >= b) as i64),
+		Name::Le => Integer((a <= b) as i64),
+		_ => Error(format!("{:?} is not defined for Decimals", name)),
 	}
 }
@@ -0,0 +1,296 @@
+/*
+runs a reduced program
+
+points are pulled, a point is only evaluated when something asks for its
+value, and then remembered until an input, a clock or a register it
+depends on changes, then only the points that depend on it are pulled
+again. the branch of an if that isn't taken is never evaluated
+
+an index outside its array wraps around and sets the overflow flag of
+the point, which is shown after its value
+
+a clock or an input fires, a clock flips its phase. every latch whose
+signal is computed from what fired takes its value, then they're all
+written to their registers at once, so each reads the registers as they
+were before
+*/
+
+use super::expander::is_ordinal;
+use super::ir::{Ir, Type};
+use super::parser::IDX;
+use super::reducer::{
+	execute1, execute2, operands, sources, truth, unsigned, wrap, Point,
+};
+use super::typer::{size, Typ};
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+pub struct Runtime {
+	code: Rc<Vec<Point>>, // shared, a point is read while others are got
+	types: Vec<Type>,
+	inputs: BTreeMap<IDX, Point>,
+	memory: BTreeMap<IDX, Point>, // registers, and the phase of clocks
+	latches: Vec<(IDX, BTreeSet<IDX>)>, // and what fires them
+	dependents: Vec<Vec<IDX>>,    // the points that need each point
+	cache: Vec<Option<Point>>,
+	pub overflows: BTreeSet<IDX>, // points whose index wrapped
+}
+
+// the value of every labelled point of the program root
+pub fn runtime(ir: &Ir) -> Result<String, String> {
+	let mut runtime = Runtime::new(ir);
+	let mut output = String::new();
+	for (path, idx) in &ir.labels {
+		let label = match path.strip_prefix("root.") {
+			Some(label) if !label.contains('.') => label,
+			_ => continue,
+		};
+		if !is_ordinal(label) {
+			match runtime.show(*idx) {
+				Ok(value) if runtime.overflows.contains(idx) => output
+					.push_str(&format!(
+						"{}: {} @overflow\n",
+						label, value
+					)),
+				Ok(value) => {
+					output.push_str(&format!("{}: {}\n", label, value))
+				}
+				Err(e) => output.push_str(&format!("{}: {}\n", label, e)),
+			}
+		}
+	}
+	Ok(output)
+}
+
+impl Runtime {
+	pub fn new(ir: &Ir) -> Self {
+		let code = &ir.code;
+		let mut latches = Vec::new();
+		let mut dependents = vec![Vec::new(); code.len()];
+		for (idx, point) in code.iter().enumerate() {
+			if let Point::Latch(_, s, _) = point {
+				latches.push((idx, sources(code, *s)));
+			}
+			for operand in operands(point) {
+				dependents[operand].push(idx);
+			}
+		}
+		Runtime {
+			code: Rc::new(code.clone()),
+			types: ir.types.clone(),
+			inputs: BTreeMap::new(),
+			memory: BTreeMap::new(),
+			latches,
+			dependents,
+			cache: vec![None; code.len()],
+			overflows: BTreeSet::new(),
+		}
+	}
+
+	// writes an input, and fires it
+	pub fn set(&mut self, idx: IDX, value: Point) -> Result<(), String> {
+		self.write(idx, value)?;
+		self.latch(idx)
+	}
+
+	// writes an input without firing it, no latch takes a value
+	pub fn write(&mut self, idx: IDX, value: Point) -> Result<(), String> {
+		match self.code.get(idx) {
+			Some(Point::Input(_)) => {
+				self.inputs.insert(idx, value);
+				self.invalidate(idx);
+				Ok(())
+			}
+			x => Err(format!("{:?} is not an Input", x)),
+		}
+	}
+
+	// a clock flips its phase, then the latches it drives are written
+	pub fn fire(&mut self, idx: IDX) -> Result<(), String> {
+		match self.code.get(idx) {
+			Some(Point::Clock(_)) => {
+				let phase = match self.memory.get(&idx) {
+					Some(Point::Integer(1)) => 0,
+					_ => 1,
+				};
+				self.memory.insert(idx, Point::Integer(phase));
+				self.invalidate(idx);
+				self.latch(idx)
+			}
+			Some(Point::Input(_)) => self.latch(idx),
+			x => Err(format!("{:?} is not a clock or an Input", x)),
+		}
+	}
+
+	fn latch(&mut self, source: IDX) -> Result<(), String> {
+		let fired: Vec<IDX> = self
+			.latches
+			.iter()
+			.filter(|(_, sources)| sources.contains(&source))
+			.map(|(idx, _)| *idx)
+			.collect();
+		let mut values = Vec::new();
+		for idx in fired {
+			if let Point::Latch(r, _, _) = self.code[idx] {
+				values.push((r, self.get(idx)?));
+			}
+		}
+		for (r, value) in values {
+			self.memory.insert(r, value);
+			self.invalidate(r);
+		}
+		Ok(())
+	}
+
+	// a point changed, what depends on it is pulled again, nothing else
+	fn invalidate(&mut self, idx: IDX) {
+		let mut seen = BTreeSet::new();
+		let mut stack = vec![idx];
+		while let Some(idx) = stack.pop() {
+			if seen.insert(idx) {
+				self.cache[idx] = None;
+				self.overflows.remove(&idx);
+				stack.extend(self.dependents[idx].iter().cloned());
+			}
+		}
+	}
+
+	// a value as it's written, a graph shows the values of its points
+	pub fn show(&mut self, idx: IDX) -> Result<String, String> {
+		if let Point::Sequence(n) = self.code[idx] {
+			return Ok(format!("{{{}, {}, {}, ..}}", n, n + 1, n + 2));
+		}
+		match self.get(idx)? {
+			Point::Integer(v) if unsigned(&self.code, idx) => {
+				Ok(format!("{}", v as u64))
+			}
+			Point::Integer(v) => Ok(format!("{}", v)),
+			Point::Decimal(v) => Ok(format!("{:?}", v)),
+			Point::Graph(points) => {
+				let mut values = Vec::new();
+				for point in points {
+					values.push(self.show(point)?);
+				}
+				Ok(format!("{{{}}}", values.join(", ")))
+			}
+			x => Ok(format!("{:?}", x)),
+		}
+	}
+
+	pub fn get(&mut self, idx: IDX) -> Result<Point, String> {
+		if let Some(value) = &self.cache[idx] {
+			return Ok(value.clone());
+		}
+		let code = Rc::clone(&self.code);
+		let value = match &code[idx] {
+			Point::Integer(_) | Point::Decimal(_) | Point::Graph(_) => {
+				self.code[idx].clone()
+			}
+			Point::Input(name) => match self.inputs.get(&idx) {
+				Some(value) => value.clone(),
+				None => match size(*name) {
+					Typ::Decimal => Point::Decimal(0.0),
+					_ => Point::Integer(0),
+				},
+			},
+			Point::Op2(name, a, b) => {
+				let a = self.get(*a)?;
+				let b = self.get(*b)?;
+				match execute2(*name, &a, &b) {
+					Point::Error(e) => return Err(e),
+					value => value,
+				}
+			}
+			Point::Op1(name, a) => {
+				let a = self.get(*a)?;
+				match execute1(*name, &a) {
+					Point::Error(e) => return Err(e),
+					value => value,
+				}
+			}
+			Point::Filter(a, m) => {
+				let (points, mask) = match (self.get(*a)?, self.get(*m)?) {
+					(Point::Graph(points), Point::Graph(mask)) => {
+						(points, mask)
+					}
+					(x, y) => {
+						return Err(format!(
+							"{:?} can't be filtered by {:?}",
+							x, y
+						))
+					}
+				};
+				let mut selected = Vec::new();
+				for (point, m) in points.into_iter().zip(mask) {
+					let m = self.get(m)?;
+					match truth(&m) {
+						Some(true) => selected.push(point),
+						Some(false) => {}
+						None => {
+							return Err(format!("{:?} is not a mask", m))
+						}
+					}
+				}
+				Point::Graph(selected)
+			}
+			Point::Tally(a) => match self.get(*a)? {
+				Point::Graph(points) => {
+					Point::Integer(points.len() as i64)
+				}
+				x => return Err(format!("{:?} is not an array", x)),
+			},
+			Point::If(c, a, b) => {
+				let c = self.get(*c)?;
+				match truth(&c) {
+					Some(true) => self.get(*a)?,
+					Some(false) => self.get(*b)?,
+					None => {
+						return Err(format!("{:?} is not a condition", c))
+					}
+				}
+			}
+			Point::Index(a, i) => {
+				let points = match self.get(*a)? {
+					Point::Graph(points) => points,
+					x => return Err(format!("{:?} is not an array", x)),
+				};
+				match self.get(*i)? {
+					Point::Integer(v) if points.is_empty() => {
+						return Err(format!(
+							"index {} of an empty array",
+							v
+						))
+					}
+					Point::Integer(v) => {
+						if v < 0 || v as usize >= points.len() {
+							self.overflows.insert(idx);
+						}
+						self.get(points[wrap(v, points.len())])?
+					}
+					x => return Err(format!("{:?} is not an index", x)),
+				}
+			}
+			Point::Clock(_) => match self.memory.get(&idx) {
+				Some(phase) => phase.clone(),
+				None => Point::Integer(0),
+			},
+			Point::Register => match self.memory.get(&idx) {
+				Some(value) => value.clone(),
+				None if self.types[idx] == Type::Decimal => {
+					Point::Decimal(0.0)
+				}
+				None => Point::Integer(0),
+			},
+			Point::Latch(_, _, v) => self.get(*v)?,
+			Point::Sequence(_) => {
+				return Err(
+					"an unbounded sequence is never whole".to_string()
+				)
+			}
+			Point::Error(e) => return Err(e.clone()),
+			Point::Dummy => return Err(format!("{} is a Dummy", idx)),
+		};
+		self.cache[idx] = Some(value.clone());
+		Ok(value)
+	}
+}
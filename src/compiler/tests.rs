@@ -0,0 +1,715 @@
+/*
+every test compiles its source through each stage, prints the .rplir
+and runs what's parsed back from it, the same as rpl does, so the
+runtime and the round trip are checked together
+*/
+
+use super::expander::{self, Kind, Maps, Sizes, Strategy};
+use super::parser::Ast;
+use super::reducer::Point;
+use super::runtime::Runtime;
+use super::{dot, transpiler, typer};
+use super::{eliminator, ir, parser, reducer, runtime, tokenizer};
+use crate::repl::Repl;
+
+fn expand(
+	source: &str,
+	strategy: Strategy,
+) -> Result<(Ast, Maps, Sizes), String> {
+	let tokens = tokenizer::tokenizer(source)?;
+	let parse = parser::parser(&tokens)?;
+	let spans = parser::spans(&tokens);
+	expander::expander(&parse, &spans, strategy)
+}
+
+fn compile(source: &str) -> Result<ir::Ir, String> {
+	build(source, Strategy::Selective)
+}
+
+fn build(source: &str, strategy: Strategy) -> Result<ir::Ir, String> {
+	let (expand, maps, _) = expand(source, strategy)?;
+	typer::typer(&expand, &maps)?;
+	let mut program = reducer::reducer(&expand, &maps)?;
+	eliminator::eliminator(&mut program, &[])?;
+	let text = ir::print(&ir::ir(&program));
+	let ir = ir::parse(&text)?;
+	assert_eq!(ir::print(&ir), text, "the .rplir doesn't round trip");
+	Ok(ir)
+}
+
+// the .run output, or the error that stopped it
+fn run(source: &str) -> String {
+	match compile(source).and_then(|ir| runtime::runtime(&ir)) {
+		Ok(output) => output,
+		Err(e) => e,
+	}
+}
+
+// the value of a root point once the inputs are written
+fn with(source: &str, inputs: &[(&str, Point)], label: &str) -> String {
+	let ir = compile(source).unwrap();
+	let mut runtime = Runtime::new(&ir);
+	for (input, value) in inputs {
+		let idx = ir.labels[&format!("root.{}", input)];
+		runtime.set(idx, value.clone()).unwrap();
+	}
+	runtime.show(ir.labels[&format!("root.{}", label)]).unwrap()
+}
+
+fn c(source: &str) -> Result<String, String> {
+	transpiler::transpiler(&compile(source)?)
+}
+
+#[test]
+fn if_else() {
+	let source = "a: if (1) 2 else 3\nx: [i32]\nb: if (x > 0) 10 else 20";
+	assert_eq!(run(source), "a: 2\nb: 20\nx: 0\n");
+	assert_eq!(with(source, &[("x", Point::Integer(4))], "b"), "10");
+	assert!(c(source).is_ok());
+	assert!(run("a: if (1) 1.5 else 2").contains("TypeError"));
+}
+
+#[test]
+fn if_graphs() {
+	let source = "x: [i32]\nb: if (x) {1, 2} else {3, 4}\nr: if (x) (<3) else {3, 4, 5}";
+	assert_eq!(with(source, &[("x", Point::Integer(1))], "b"), "{1, 2}");
+	assert_eq!(
+		with(source, &[("x", Point::Integer(0))], "r"),
+		"{3, 4, 5}"
+	);
+	assert!(c(source).is_ok());
+	// what the c can't pick between isn't typed
+	for branches in [
+		"{1, 2} else {3}",
+		"{1, 2} else {1.5, 4}",
+		"{{1}, {2}} else {{1, 2}, {3}}",
+		"{1, 2} else 3",
+		"(<3) else {3, 4}",
+		"f else {1}",
+	] {
+		let source = format!(
+			"x: [i32]\na: {{1, 2}}\nf: a ` {{1, x}}\nb: if (x) {}",
+			branches
+		);
+		assert!(run(&source).contains("TypeError"), "{}", branches);
+	}
+}
+
+#[test]
+fn match_arms() {
+	let source = "x: [i32]\nm: { x < 0 -> 0 - x, _ -> x }";
+	assert_eq!(with(source, &[("x", Point::Integer(-3))], "m"), "3");
+	assert_eq!(with(source, &[("x", Point::Integer(4))], "m"), "4");
+	assert_eq!(run("n: { 1 > 2 -> 1, _ -> 7 }"), "n: 7\n");
+	assert!(c(source).is_ok());
+	// a label other than _ is a guard, it holds when it isn't 0
+	let source = "x: [i32]\nm: { x -> 1, _ -> 2 }";
+	assert_eq!(with(source, &[("x", Point::Integer(5))], "m"), "1");
+	assert_eq!(with(source, &[("x", Point::Integer(0))], "m"), "2");
+	assert!(run("m: { y -> 1, _ -> 2 }").contains("y is undefined"));
+	// a match without an arm that always holds is a warning, running out
+	// of arms is an error when it's run
+	let source = "x: [i32]\nm: { x < 0 -> 1, x > 0 -> 2 }";
+	assert_eq!(with(source, &[("x", Point::Integer(3))], "m"), "2");
+	assert_eq!(
+		run(source),
+		"m: no arm of the match in root.m holds\nx: 0\n"
+	);
+	let c = c(source).unwrap();
+	assert!(c.contains("_error = 1;"), "{}", c);
+	assert_eq!(
+		run("m: { 1 > 2 -> 1 }"),
+		"m: no arm of the match in root.m holds\n"
+	);
+	assert_eq!(run("m: { 1 > 0 -> 1, 2 > 3 -> 2 }"), "m: 1\n");
+	// the typer can't tell x < 0 and x >= 0 cover every x
+	let source = "x: [i32]\nm: {x < 0 -> 1, x >= 0 -> 2}";
+	assert_eq!(run(source), "m: 2\nx: 0\n");
+	let warnings = |source: &str| {
+		let tokens = tokenizer::tokenizer(source).unwrap();
+		let parse = parser::parser(&tokens).unwrap();
+		let spans = parser::spans(&tokens);
+		let (expand, maps, _) =
+			expander::expander(&parse, &spans, Strategy::Selective)
+				.unwrap();
+		typer::typer(&expand, &maps).unwrap().1
+	};
+	assert_eq!(
+		warnings(source),
+		["Warning: the match in m may not be exhaustive, no arm always holds, end it with an arm like _ -> value"]
+	);
+	assert!(warnings("x: [i32]\nm: {x < 0 -> 1, _ -> 2}").is_empty());
+}
+
+#[test]
+fn index_ranges() {
+	assert_eq!(
+		run("a: {1, 2, 3, 4, 5}\nb: a#[1]\nc: a#[> 2]\nd: a#[0 < i < 3]\ng: a#[5 > j >= 1]"),
+		"a: {1, 2, 3, 4, 5}\nb: 2\nc: {4, 5}\nd: {2, 3}\ng: {5, 4, 3, 2}\n"
+	);
+}
+
+#[test]
+fn selectors() {
+	let source = "base: {10, jef: 20, 30, bob: 40}\nc: base.(2)\nn: [i8]\nd: base.(n + 1)\ne: base.{0, 3}\nf: base.[jef, bob]\nk: {{1, 2}, {3, 4}}.1.0";
+	assert_eq!(
+		run(source),
+		"base: {10, 20, 30, 40}\nc: 30\nd: 20\ne: {10, 40}\nf: {20, 40}\nk: 3\nn: 0\n"
+	);
+	assert_eq!(with(source, &[("n", Point::Integer(1))], "d"), "30");
+	assert!(c(source).is_ok());
+	// a label is selected from the graph, not the scopes around it
+	assert!(run("b: 5\nq: {a: 1}\nx: q.b").contains("q has no label b"));
+}
+
+#[test]
+fn maps() {
+	let source = "q: {a: {b: 1, 2}}\nPoint {x, y} ~ Point {x: 3, y: 4}";
+	let (_, maps, _) = expand(source, Strategy::Selective).unwrap();
+	let b = maps.at("root.q.a.b").unwrap();
+	assert_eq!(b.kind, Kind::Point);
+	assert_eq!(maps.scopes[b.parent].path, "root.q.a");
+	assert_eq!(maps.paths["root.q.a"], b.parent);
+	assert_eq!(maps.at("root.q.a.1").unwrap().kind, Kind::Ordinal);
+	assert_eq!(maps.at("root.x").unwrap().kind, Kind::Alias);
+	assert!(maps.at("root.q.z").is_none());
+	// the prelude is the parent of the root, its points are found by path
+	assert_eq!(maps.parent(maps.paths["root"]), maps.paths["prelude"]);
+	assert!(maps.at("prelude.Sqrt.n").is_some());
+}
+
+#[test]
+fn undefined() {
+	// a point is looked for up through the scopes around it
+	assert_eq!(
+		run("jeff: 1\nq: {a: jef}"),
+		"Point jef is undefined, searched root.q, root and prelude\ndid you mean jeff (root)?"
+	);
+	// a selection only looks in the graph, and the graphs in it
+	let source = "jef: 5\nq: {a: 1, jeff: 2, r: {jef: 3}}";
+	let error = "q has no label jef\ndid you mean jeff?\nq.r has a jef, select it with q.r.jef";
+	assert_eq!(run(&format!("{}\nx: q.jef", source)), error);
+	assert_eq!(run(&format!("{}\nx: q.[a, jef]", source)), error);
+	assert_eq!(run("q: {a: 1}\nx: q.b"), "q has no label b");
+}
+
+#[test]
+fn positional_keys() {
+	assert_eq!(
+		run("jeff: 5\ng: {1, 2, bob: 10, (jeff), tim: 20}\na: g.3\nc: g#[1 < 4]"),
+		"a: 5\nc: {10, 5}\ng: {1, 2, 10, 5, 20}\njeff: 5\n"
+	);
+	assert!(expander::is_ordinal("12"));
+	assert!(!expander::is_ordinal(""));
+	assert!(!expander::is_ordinal("x1"));
+}
+
+#[test]
+fn overrides() {
+	let config = "Config: {net: {host: 1, port: 8}, debug: 0}\nc: Config ";
+	for (modifier, value) in [
+		("{net.port: 80}", "{{1, 80}, 0}"),
+		("{net: {port: 80}}", "{{1, 80}, 0}"),
+		("{net.port: 80} {debug: 1}", "{{1, 80}, 1}"),
+		("{+extra: 5}", "{{1, 8}, 0, 5}"),
+	] {
+		assert_eq!(
+			run(&format!("{}{}", config, modifier)),
+			format!("Config: {{{{1, 8}}, 0}}\nc: {}\n", value),
+			"{}",
+			modifier
+		);
+	}
+	let deep =
+		"D: {a: {b: {c: 1, d: 2}}}\nx: D {a.b.c: 5} {a: {b: {d: 6}}}";
+	assert!(run(deep).ends_with("x: {{{5, 6}}}\n"));
+	assert!(run(&format!("{}{{nope: 1}}", config))
+		.contains("nope is not a point of Config, it has net, debug"));
+	assert!(run(&format!("{}{{net.nope: 1}}", config))
+		.contains("it has host, port, add it with +nope"));
+}
+
+#[test]
+fn apply_kinds() {
+	for (source, error) in [
+		("a: 10 {x: 1}", "TypeError: 10 is an Integer"),
+		("a: 1.5 {x: 1}", "TypeError: 1.5 is a Decimal"),
+		("a: 1s {x: 1}", "TypeError: 1s is a Clock"),
+		("x: [i32]\na: x {y: 1}", "TypeError: x is a single value"),
+		(
+			"A: [4 i32]\na: A {x: 5}",
+			"A is an array, its points are replaced by position, like A {1: 5}, not by label x",
+		),
+		(
+			"a: [4 i32] {x: 5}",
+			"[4 i32] is an array, its points are replaced by position",
+		),
+		(
+			"A: [4 i32]\na: A {7: 5}",
+			"position 7 is out of bounds of A, an array of 4",
+		),
+		(
+			"x: [i32]\nM: {x < 0 -> 1, _ -> 2}\nm: M {x: 1}",
+			"M is a match, it has arms, not points to replace",
+		),
+	] {
+		assert!(run(source).starts_with(error), "{}", run(source));
+	}
+	// an array is replaced by position, a sized one keeps its size
+	assert!(run("A: [4 i32]\na: A {1: 5}").ends_with("a: {0, 5, 0, 0}\n"));
+	assert_eq!(
+		run("a: [4 i32]! {1, 2, 3, 4} {1: 20}"),
+		"a: {1, 20, 3, 4}\n"
+	);
+}
+
+#[test]
+fn strategies() {
+	let source = "x: [i32]\nT: {a: x * 2, b: a + 1, c: 7}\np: T {c: 8}\nq: T {c: x}";
+	let (_, _, selective) = expand(source, Strategy::Selective).unwrap();
+	let (_, _, full) = expand(source, Strategy::Full).unwrap();
+	// either strategy counts both, the instances share a and b of T
+	assert_eq!(selective.full(), full.full());
+	assert_eq!(selective.selective(), full.selective());
+	assert_eq!(selective.full() - selective.selective(), 8);
+	let report = selective.report();
+	assert!(report.starts_with("strategy: Selective\n"));
+	assert!(report
+		.contains("root.p shares 2 of 3 points of root.T, 4 nodes: a, b"));
+	assert!(full.report().starts_with("strategy: Full\n"));
+	// and they compute the same values
+	for strategy in [Strategy::Selective, Strategy::Full] {
+		let ir = build(source, strategy).unwrap();
+		let mut runtime = Runtime::new(&ir);
+		runtime.set(ir.labels["root.x"], Point::Integer(3)).unwrap();
+		for (label, value) in [("p", "{6, 7, 8}"), ("q", "{6, 7, 3}")] {
+			let idx = ir.labels[&format!("root.{}", label)];
+			assert_eq!(runtime.show(idx).unwrap(), value);
+		}
+	}
+}
+
+#[test]
+fn interned() {
+	let ir =
+		compile("x: [i32]\na: x * 2 + 1\nb: x * 2 + 1\nc: x * 3").unwrap();
+	assert_eq!(ir.labels["root.a"], ir.labels["root.b"]);
+	assert_eq!((ir.pushed, ir.code.len()), (12, 8));
+	assert!(ir::print(&ir)
+		.contains("reduced 12 points to 8, 4 joined, 0 dead"));
+	// the points of every copy a full expansion makes are one point
+	let source = "x: [i32]\nT: {a: x + 1, b: 5}\np: T {b: 6}\nq: T {b: 7}";
+	let ir = build(source, Strategy::Full).unwrap();
+	assert_eq!(ir.labels["root.p.a"], ir.labels["root.q.a"]);
+	assert_eq!(ir.labels["root.T.a"], ir.labels["root.q.a"]);
+	// inputs are never joined, each is written on its own
+	let ir = compile("x: [i32]\ny: [i32]").unwrap();
+	assert_ne!(ir.labels["root.x"], ir.labels["root.y"]);
+}
+
+#[test]
+fn dead() {
+	// the .dead report, once what isn't kept is removed
+	let dead = |source: &str, keep: &[&str]| {
+		let (expand, maps, _) = expand(source, Strategy::Selective)?;
+		let mut program = reducer::reducer(&expand, &maps)?;
+		let keep: Vec<String> =
+			keep.iter().map(|k| k.to_string()).collect();
+		eliminator::eliminator(&mut program, &keep)
+			.map(|report| (report, program.code.len()))
+	};
+	let source = "x: [i32]\nT: {a: x + 1, b: x * 7, -> a}";
+	let (report, len) = dead(source, &[]).unwrap();
+	assert!(report.starts_with("kept 4 of 6 points, roots: 1 inputs, 0 clocks, 2 observed, 0 returns, 0 kept"));
+	assert!(report.contains("removed root.T.b: "));
+	assert!(report.contains("removed 1 unnamed constants"));
+	assert_eq!(len, 4);
+	// a point pinned is kept with what it needs
+	let (report, len) = dead(source, &["root.T.b"]).unwrap();
+	assert!(report.starts_with("kept 6 of 6 points"));
+	assert!(report.ends_with("1 kept\n"));
+	assert_eq!(len, 6);
+	assert!(dead(source, &["root.T.z"])
+		.unwrap_err()
+		.contains("--keep root.T.z is not a point"));
+	// the registers a kept point reads keep what's latched into them
+	let (report, _) = dead("c: 1s\nn: c ? n + 1", &[]).unwrap();
+	assert!(report
+		.starts_with("kept 6 of 6 points, roots: 0 inputs, 1 clocks"));
+}
+
+#[test]
+fn bleed() {
+	assert_eq!(
+		run("a: {x: 1, y: 2}\nb: {..a, y: 5, z: 6}\nc: {1, 2} .. {3}\ne: b.y"),
+		"a: {1, 2}\nb: {1, 5, 6}\nc: {1, 2, 3}\ne: 5\n"
+	);
+	assert!(run("c: {1, 2} .. {3}\ng: [2 i32]! c").contains("capacity"));
+}
+
+#[test]
+fn shape() {
+	assert_eq!(
+		run("a: {1, 2, 3, 4, 5, 6}\nb: a $ [2 3]\nc: $b\nd: b.1.2"),
+		"a: {1, 2, 3, 4, 5, 6}\nb: {{1, 2, 3}, {4, 5, 6}}\nc: {2, 3}\nd: 6\n"
+	);
+	assert!(run("a: {1, 2, 3}\nb: a $ [2 2]").contains("can't be shaped"));
+	// a dimension of 0 has no values to shape
+	assert_eq!(run("a: {} $ [0 3]"), "a: {}\n");
+	assert_eq!(run("a: {} $ [3 0]"), "a: {{}, {}, {}}\n");
+	assert!(run("a: [0 3 i32]").contains("holds no inputs"));
+	assert!(run("a: [3 0 i32]").contains("holds no inputs"));
+	// the rows of a reshaped array have the type of its values
+	let source = "x: [i32]\na: {1.5, 2.5, 3.5, 4.5}\nb: a $ [2 2]";
+	assert_eq!(
+		run(&format!("{}\nc: if (x) b.1.1 else 2.0\nd: b.0 * 2", source)),
+		"a: {1.5, 2.5, 3.5, 4.5}\nb: {{1.5, 2.5}, {3.5, 4.5}}\nc: 2.0\nd: {3.0, 5.0}\nx: 0\n"
+	);
+	assert!(run(&format!("{}\nc: if (x) b.1.1 else 2", source))
+		.contains("TypeError"));
+	assert!(run(&format!("{}\nc: if (x) b.0 else {{1, 2}}", source))
+		.contains("TypeError"));
+}
+
+#[test]
+fn broadcast() {
+	let source = "a: {1, 2, 3}\nc: a + {10, 20, 30}\nn: {{1, 2}, {3, 4}} * 10 + 1\nx: [i32]\ny: a * x\nw: a != 2\np: {1, 2.5} + 1";
+	assert_eq!(
+		run(source),
+		"a: {1, 2, 3}\nc: {11, 22, 33}\nn: {{11, 21}, {31, 41}}\np: {2, 3.5}\nw: {1, 0, 1}\nx: 0\ny: {0, 0, 0}\n"
+	);
+	assert_eq!(
+		with(source, &[("x", Point::Integer(2))], "y"),
+		"{2, 4, 6}"
+	);
+	assert!(c(source).is_ok());
+	// the single values only a loop reads aren't computed on their own
+	let c = c("in: [3 i32]\nb: in * 2 + 1").unwrap();
+	assert!(!c
+		.lines()
+		.any(|l| l.starts_with("static int64_t p") && !l.contains('[')));
+	assert_eq!(
+		run("m: [4 f32]! {0}\nj: m + 2\nk: [2 2 i32]! {1, 2, 3}"),
+		"j: {2.0, 2.0, 2.0, 2.0}\nk: {{1, 2}, {3, 0}}\nm: {0.0, 0.0, 0.0, 0.0}\n"
+	);
+}
+
+#[test]
+fn iota() {
+	assert_eq!(
+		run("a: (<7)\nd: (>7)#[< 3]\nf: [4]! (>=10)"),
+		"a: {0, 1, 2, 3, 4, 5, 6}\nd: {8, 9, 10}\nf: {10, 11, 12, 13}\n"
+	);
+	// a sequence is only sized or indexed, it never ends to be a value
+	for (source, error) in [
+		("c: >7", "TypeError: c is an unbounded sequence"),
+		(
+			"g: {1, >7}",
+			"TypeError: in g, point 1 is an unbounded sequence",
+		),
+		(
+			"a: (>7) + 1",
+			"TypeError: in a, Add of an unbounded sequence",
+		),
+		(
+			"x: [i32]\nb: if (x) (>7) else (<3)",
+			"TypeError: in b, a branch of if is an unbounded sequence",
+		),
+	] {
+		assert!(run(source).starts_with(error), "{}", run(source));
+	}
+}
+
+#[test]
+fn filter() {
+	let source =
+		"a: {1, 2, 3, 4}\nx: [i32]\nf: a ` (a > x)\nn: #f\nu: [u8]\nv: `u";
+	assert_eq!(
+		run(source),
+		"a: {1, 2, 3, 4}\nf: {1, 2, 3, 4}\nn: 4\nu: 0\nv: 255\nx: 0\n"
+	);
+	assert_eq!(with(source, &[("x", Point::Integer(2))], "f"), "{3, 4}");
+	assert_eq!(with(source, &[("x", Point::Integer(2))], "n"), "2");
+	assert!(c(source).is_ok());
+}
+
+#[test]
+fn tally() {
+	let source = "a: {1, 2, 3, 4}\nx: [i32]\nb: a#x\nn: #[3 4 u8]\nf: a ` (a > 1)\nt: #f";
+	assert_eq!(
+		run(source),
+		"a: {1, 2, 3, 4}\nb: 1\nf: {2, 3, 4}\nn: 12\nt: 3\nx: 0\n"
+	);
+	assert_eq!(with(source, &[("x", Point::Integer(5))], "b"), "2");
+	assert!(c(source).unwrap().contains("static uint8_t p"));
+}
+
+#[test]
+fn unary() {
+	let source =
+		"a: 10 + /10\nb: +(0 - 5)\ne: /{1, 2, 4}\nx: [i32]\ng: /x";
+	assert_eq!(
+		run(source),
+		"a: 10.1\nb: 5\ne: {1.0, 0.5, 0.25}\ng: /0 divides by zero\nx: 0\n"
+	);
+	assert_eq!(with(source, &[("x", Point::Integer(4))], "g"), "0.25");
+	// the bits of an unsigned input are only its own, a u64 has all 64
+	let source = "x: [u8]\nn: `x\ny: [u64]\nm: `y";
+	assert_eq!(
+		run(source),
+		"m: 18446744073709551615\nn: 255\nx: 0\ny: 0\n"
+	);
+	assert_eq!(
+		with(source, &[("y", Point::Integer(5))], "m"),
+		"18446744073709551610"
+	);
+}
+
+#[test]
+fn c_arithmetic() {
+	// what the runtime raises as an error sets an error flag in c, and
+	// integers wrap in both
+	let source =
+		"x: [i32]\ny: 10 / x\nz: 3 ^ x\nv: /x\nm: x * 2\nw: {1, 2} / x";
+	let output = run(source);
+	assert!(output.contains("y: 10 / 0 divides by zero"), "{}", output);
+	let c = c(source).unwrap();
+	for expression in [
+		"rpl_div(10, p0, &p",
+		"rpl_pow(3, p0, &p",
+		"rpl_inv(p0, &p",
+		"(int64_t)((uint64_t)p0 * (uint64_t)2)",
+		"] = rpl_div(p",
+	] {
+		assert!(c.contains(expression), "{}\n{}", expression, c);
+	}
+	let flags = c.lines().filter(|l| l.ends_with("_error;")).count();
+	let cleared = c.lines().filter(|l| l.ends_with("_error = 0;")).count();
+	assert_eq!((flags, cleared), (4, 4), "{}", c);
+	assert!(run("a: 2 ^ (0 - 1)").contains("is not an Integer"));
+}
+
+#[test]
+fn pun() {
+	assert_eq!(
+		run("x: 5\nP: {x: y: 0}\np: P { x }"),
+		"P: {0, 0}\np: {5, 0}\nx: 5\n"
+	);
+	assert_eq!(run("x: 5\nh: {x: 1, k: {x}}"), "h: {1, {1}}\nx: 5\n");
+}
+
+#[test]
+fn prelude_sqrt() {
+	let source = "n: [i32]\nr: Sqrt { n }\nq: Sqrt(n + 1)";
+	for (n, r) in [(0, 0), (1, 1), (80, 8), (81, 9), (2147483647, 46340)] {
+		assert_eq!(
+			with(source, &[("n", Point::Integer(n))], "r"),
+			r.to_string()
+		);
+	}
+	// a negative n has no root
+	assert_eq!(with(source, &[("n", Point::Integer(-5))], "r"), "0");
+	assert_eq!(with(source, &[("n", Point::Integer(80))], "q"), "9");
+	assert_eq!(run("disc: 49\nr: Sqrt(disc)"), "disc: 49\nr: 7\n");
+	assert_eq!(run("P: {x: y: 0}\np: P(3)"), "P: {0, 0}\np: {3, 0}\n");
+}
+
+#[test]
+fn prelude_signals() {
+	let source = "x: [i32]\nc: counter { clock: 60ms }\nn: count(100ms)\nr: rising { input: x }\nf: falling { input: x }\nw: window'4 { input: x }";
+	let ir = compile(source).unwrap();
+	let at = |path: &str| ir.labels[&format!("root.{}", path)];
+	let mut runtime = Runtime::new(&ir);
+	for _ in 0..3 {
+		runtime.fire(at("c.clock")).unwrap();
+	}
+	assert_eq!(runtime.show(at("c")).unwrap(), "3");
+	assert_eq!(runtime.show(at("n")).unwrap(), "0");
+	runtime.fire(at("n.clock")).unwrap();
+	assert_eq!(runtime.show(at("n")).unwrap(), "1");
+	// input against what it was on the last tick
+	runtime.set(at("x"), Point::Integer(8)).unwrap();
+	assert_eq!(runtime.show(at("r")).unwrap(), "1");
+	assert_eq!(runtime.show(at("f")).unwrap(), "0");
+	runtime.fire(at("r.clock")).unwrap();
+	assert_eq!(runtime.show(at("r")).unwrap(), "0");
+	for (ticks, average) in [(1, "2"), (1, "4"), (2, "8")] {
+		for _ in 0..ticks {
+			runtime.fire(at("w.clock")).unwrap();
+		}
+		assert_eq!(runtime.show(at("w")).unwrap(), average);
+	}
+	assert!(c(source).is_ok());
+}
+
+#[test]
+fn pattern_duplicates() {
+	assert!(run("Point {x: a, y: a} :: Point {x: 1, y: 2}")
+		.contains("a is bound twice"));
+	assert!(run("Point {x, y} ~ Point {x: 1, y: 2}\nPoint {x, y} ~ Point {x: 3, y: 4}")
+		.contains("x is bound twice"));
+	assert!(run("x: 1\nPoint {x, y} ~ Point {x: 1, y: 2}")
+		.contains("x is bound twice"));
+	// the same labels in a graph of their own are fine
+	assert_eq!(
+		run("Point {x, y} ~ Point {x: 1, y: 2}\nz: x + y\nw: { Point {x, y} ~ Point {x: 3, y: 4} }"),
+		"w: {{3, 4}}\nz: 3\n"
+	);
+}
+
+#[test]
+fn cycles() {
+	assert_eq!(run("a: { b: a.c, c: 1 }"), "a: {1, 1}\n");
+	assert_eq!(run("q: {s: {t: q.s.u, u: 2}}"), "q: {{2, 2}}\n");
+	for source in ["a: a + 1", "a: {b: a.b}", "a: { b: a.c, c: a.b }"] {
+		let e = run(source);
+		assert!(e.starts_with("Cycle: ") && !e.contains("clock"), "{}", e);
+	}
+	assert!(run("a: { b: a.z, c: 1 }").contains("a has no label z"));
+}
+
+#[test]
+fn ir_kinds() {
+	let ok = "rplir 1\n%0 i64 @0 = input i32\n%1 i64 @1 = input i32\n%2 graph[2] = graph %0 %1\n%3 i64 = int 2\n%4 i64 @2 = op2 mul %0 %3\n%5 i64 @3 = op2 mul %1 %3\n%6 graph[2] = graph %4 %5\n%7 graph[1] = graph %0\nloop %6 = mul %2 %3\nroot = %6\n";
+	assert!(ir::parse(ok).is_ok());
+	let short = ok.replace("loop %6 = mul %2 %3", "loop %6 = mul %7 %3");
+	assert!(ir::parse(&short).unwrap_err().contains("graph[2]"));
+	let scalar = ok.replace("loop %6", "loop %5");
+	assert!(ir::parse(&scalar).unwrap_err().contains("computes a graph"));
+	for bad in [
+		"%1 i64 @0 = tally %0",
+		"%1 i64 @0 = index %0 %0",
+		"%1 array[0] @0 = filter %0 %0",
+	] {
+		let text =
+			format!("rplir 1\n%0 i64 = int 1\n{}\nroot = %1\n", bad);
+		assert!(
+			ir::parse(&text).unwrap_err().contains("needs %0"),
+			"{}",
+			bad
+		);
+	}
+	let graph = "rplir 1\n%0 i64 = int 1\n%1 graph[1] = graph %0\n%2 i64 @0 = op2 add %1 %0\nroot = %2\n";
+	assert!(ir::parse(graph).unwrap_err().contains("a single value"));
+}
+
+#[test]
+fn signals() {
+	let source =
+		"c: 1s\nx: [i32]\nn: c ? n + 1\nd: c ? d + 0.5\ne: x ? e + x";
+	let ir = compile(source).unwrap();
+	let at = |label: &str| ir.labels[&format!("root.{}", label)];
+	let mut runtime = Runtime::new(&ir);
+	assert_eq!(runtime.show(at("n")).unwrap(), "0");
+	for _ in 0..3 {
+		runtime.fire(at("c")).unwrap();
+	}
+	assert_eq!(runtime.show(at("c")).unwrap(), "1");
+	assert_eq!(runtime.show(at("n")).unwrap(), "3");
+	assert_eq!(runtime.show(at("d")).unwrap(), "1.5");
+	// writing an input fires it
+	runtime.set(at("x"), Point::Integer(4)).unwrap();
+	runtime.set(at("x"), Point::Integer(5)).unwrap();
+	assert_eq!(runtime.show(at("e")).unwrap(), "9");
+	assert_eq!(runtime.show(at("n")).unwrap(), "3");
+	assert!(runtime.fire(at("n")).is_err());
+	assert!(c(source).is_ok());
+	// a register is typed by what's latched into it
+	let tokens = tokenizer::tokenizer(source).unwrap();
+	let parse = parser::parser(&tokens).unwrap();
+	let spans = parser::spans(&tokens);
+	let (expand, maps, _) =
+		expander::expander(&parse, &spans, Strategy::Selective).unwrap();
+	let (typs, _) = typer::typer(&expand, &maps).unwrap();
+	assert_eq!(typs[1]["n"], typer::Typ::Integer);
+	assert_eq!(typs[1]["d"], typer::Typ::Decimal);
+
+	// every latch reads the registers as they were before any is written
+	let source = "c: 1s\na: c ? b + 1\nb: c ? a + 10";
+	let ir = compile(source).unwrap();
+	let mut runtime = Runtime::new(&ir);
+	let c = ir.labels["root.c"];
+	runtime.fire(c).unwrap();
+	runtime.fire(c).unwrap();
+	assert_eq!(runtime.show(ir.labels["root.a"]).unwrap(), "11");
+	assert_eq!(runtime.show(ir.labels["root.b"]).unwrap(), "11");
+
+	assert!(run("c: 1s\ng: c ? {1, 2}").contains("gates a graph"));
+	let latch = "rplir 1\n%0 i64 @0 = clock 1s\n%1 i64 = int 1\n%2 i64 @1 = latch %1 %0 %1\nroot = %2\n";
+	assert!(ir::parse(latch).unwrap_err().contains("a register"));
+	let period = latch.replace("clock 1s", "clock 1x");
+	assert!(ir::parse(&period).unwrap_err().contains("a period"));
+}
+
+#[test]
+fn invalidate() {
+	// a write only pulls again what depends on it, the rest is as it was
+	let source = "i: [i32]\nj: [i32]\na: {1, 2, 3}.(i)\nb: j + 1";
+	let ir = compile(source).unwrap();
+	let at = |label: &str| ir.labels[&format!("root.{}", label)];
+	let mut runtime = Runtime::new(&ir);
+	runtime.set(at("i"), Point::Integer(4)).unwrap();
+	assert_eq!(runtime.show(at("a")).unwrap(), "2");
+	assert!(runtime.overflows.contains(&at("a")));
+	runtime.set(at("j"), Point::Integer(1)).unwrap();
+	assert_eq!(runtime.show(at("b")).unwrap(), "2");
+	assert!(runtime.overflows.contains(&at("a")));
+	runtime.set(at("i"), Point::Integer(0)).unwrap();
+	assert!(!runtime.overflows.contains(&at("a")));
+	assert_eq!(runtime.show(at("a")).unwrap(), "1");
+}
+
+#[test]
+fn dot_signals() {
+	let source = "c: 1s\nx: [i32]\nn: c ? n + 1\ne: x ? x * 2";
+	let tokens = tokenizer::tokenizer(source).unwrap();
+	let parse = parser::parser(&tokens).unwrap();
+	let spans = parser::spans(&tokens);
+	let (expand, maps, _) =
+		expander::expander(&parse, &spans, Strategy::Selective).unwrap();
+	let ir =
+		reducer::reducer(&expand, &maps).map(|program| ir::ir(&program));
+	let dot = dot::dot(&maps, &spans, ir.as_ref().map_err(|e| e.clone()));
+	assert!(dot.contains("clock 1s\", shape=circle"));
+	assert!(dot.contains("\"root.c\" -> \"root.n\" [label=\"?\""));
+	assert!(dot.contains("\"root.x\" -> \"root.e\" [label=\"?\""));
+	// the reduced points, a latch is fired by the clock and writes n
+	let ir = ir.unwrap();
+	let (c, n) = (ir.labels["root.c"], ir.labels["root.n"]);
+	assert!(dot.contains(&format!("p{} [label=\"%{} clock 1s\"", c, c)));
+	let latch = dot
+		.lines()
+		.find(|line| line.contains(&format!("\tp{} -> p", c)))
+		.unwrap();
+	assert!(latch.ends_with("[label=\"?\", style=bold];"), "{}", latch);
+	assert!(dot.contains(&format!("-> p{} [style=dashed", n)));
+}
+
+#[test]
+fn repl() {
+	let mut repl = Repl::new(Strategy::Selective);
+	let mut line = |line: &str| match repl.line(line) {
+		Some(Ok(output)) => output,
+		Some(Err(e)) => e,
+		None => ":quit".to_string(),
+	};
+	assert_eq!(line("x: [i32]"), "x: 0 (Integer)");
+	assert_eq!(line("y: x * 2"), "y: 0 (Integer)");
+	assert_eq!(line(":set x 4"), "x: 4\ny: 8");
+	// a point entered again replaces it, inputs keep their values
+	assert_eq!(line("y: x + 1"), "y: 5 (Integer)");
+	assert_eq!(line(":type y"), "y: Integer");
+	assert_eq!(line(":deps y"), "y needs x");
+	assert!(line(":set y 3").starts_with("Error: y isn't an input"));
+	// a clock is fired, what's latched is kept until the next edit
+	assert_eq!(line("w: 1s"), "w: 0 (Clock)");
+	assert_eq!(line("n: w ? n + y"), "n: 0 (Integer)");
+	assert_eq!(line(":fire w"), "w: 1\nn: 5");
+	assert_eq!(line(":fire w"), "w: 0\nn: 10");
+	assert!(line(":fire y").starts_with("Error: y isn't a clock"));
+	// an edit that doesn't compile is dropped
+	line("z: 1 +");
+	assert_eq!(line(":list"), "x: [i32]\ny: x + 1\nw: 1s\nn: w ? n + y");
+	assert_eq!(line("10 + 20"), "it: 30 (Integer)\nn: 0");
+	assert_eq!(line(":quit"), ":quit");
+}
@@ -136,7 +136,7 @@ pub struct Token {
 	pub meta: Meta,
 }
 
-pub fn tokenizer(input: &String) -> Result<Vec<Token>, String> {
+pub fn tokenizer(input: &str) -> Result<Vec<Token>, String> {
 	lazy_static! {
 		static ref SPEC: Vec<(Kind, Name, Regex)> =
 			vec![
@@ -187,6 +187,8 @@ pub fn tokenizer(input: &String) -> Result<Vec<Token>, String> {
 				// Label
 				(Kind::Word, Name::Key, Regex::new(r"^[A-Za-z'_][A-Za-z0-9'_]*:").unwrap()),
 				(Kind::Word, Name::Key, Regex::new(r"^[$#|&=`><*/^+-]+:").unwrap()),
+				// a position, only replaced in an applied graph, a {1: 5}
+				(Kind::Word, Name::Key, Regex::new(r"^[0-9]+:").unwrap()),
 				// Label as such
 				(Kind::Word, Name::Las, Regex::new(r"^[A-Za-z'_][A-Za-z0-9'_]*;").unwrap()),
 				(Kind::Word, Name::Las, Regex::new(r"^[$#|&=`><*/^+-]+;").unwrap()),
@@ -225,15 +227,16 @@ pub fn tokenizer(input: &String) -> Result<Vec<Token>, String> {
 
 				(Kind::Operator, Name::Signal, Regex::new(r"^\?").unwrap()),
 				// (Kind::Operator, Name::Label, Regex::new(r"^:").unwrap()),
+				// != before !, or it is a sizer then an =
+				(Kind::Operator, Name::Ne, Regex::new(r"^(!=)").unwrap()),
 				(Kind::Operator, Name::Sizer, Regex::new(r"^!").unwrap()),
-				(Kind::Operator, Name::Shape, Regex::new(r"^$").unwrap()),
+				(Kind::Operator, Name::Shape, Regex::new(r"^\$").unwrap()),
 				(Kind::Operator,  Name::Index, Regex::new(r"^#").unwrap()),
 
 				(Kind::Operator, Name::Or, Regex::new(r"^[|]").unwrap()),
 				(Kind::Operator, Name::And, Regex::new(r"^[&]").unwrap()),
 				(Kind::Operator, Name::Not, Regex::new(r"^[`]").unwrap()),
 				(Kind::Operator, Name::Eq, Regex::new(r"^((==)|(=))").unwrap()),
-				(Kind::Operator, Name::Ne, Regex::new(r"^(!=)").unwrap()),
 				(Kind::Operator, Name::Ge, Regex::new(r"^(>=)").unwrap()),
 				(Kind::Operator, Name::Le, Regex::new(r"^(<=)").unwrap()),
 				(Kind::Operator, Name::Gt, Regex::new(r"^(>)").unwrap()),
@@ -278,98 +281,140 @@ pub fn tokenizer(input: &String) -> Result<Vec<Token>, String> {
 	let mut last_token_was_operator = false;
 
 	'outer: while cursor < length {
+		// base.1.2 selects position 1 then 2, it isn't base 1.2
+		if let Some(t) = position(&tokens, &input[cursor..], line) {
+			cursor += t.meta.text.len();
+			last_token_was_operator = t.of.kind == Kind::Operator;
+			last_token_was_comma = false;
+			last_token_was_newline = false;
+			tokens.push(t);
+			continue 'outer;
+		}
 		for (kind, name, re) in &SPEC[..] {
-			match re.find(&input[cursor..]) {
-				Some(mat) => {
-					let token_text = &input[cursor..cursor + mat.end()];
-
-					let t = Token {
-						of: Of {
-							kind: *kind,
-							name: *name,
-						},
-						meta: Meta {
-							line,
-							text: token_text.to_string(),
-						},
-					};
-
-					match (kind, name) {
-						(Kind::Skip, _) => {}
-						//
-						(Kind::Stop, Name::Comma)
-						| (Kind::Stop, Name::Semicolon) => {
-							if last_token_was_newline {
-								tokens.pop();
-							}
-							if !last_token_was_comma {
-								tokens.push(t);
-								last_token_was_operator = false;
-								last_token_was_comma = true;
-								last_token_was_newline = false;
-							}
-						}
-						//
-						(Kind::Stop, Name::Newline) => {
-							if !last_token_was_operator
-								&& !last_token_was_comma && !last_token_was_newline
-								&& !skip_initial_newlines
-							{
-								tokens.push(t);
-								last_token_was_operator = false;
-								last_token_was_comma = false;
-								last_token_was_newline = true;
-							}
-							line += 1;
+			if let Some(mat) = re.find(&input[cursor..]) {
+				let token_text = &input[cursor..cursor + mat.end()];
+
+				let t = Token {
+					of: Of {
+						kind: *kind,
+						name: *name,
+					},
+					meta: Meta {
+						line,
+						text: token_text.to_string(),
+					},
+				};
+
+				match (kind, name) {
+					(Kind::Skip, _) => {}
+					//
+					(Kind::Stop, Name::Comma)
+					| (Kind::Stop, Name::Semicolon) => {
+						if last_token_was_newline {
+							tokens.pop();
 						}
-						//
-						(Kind::Paren, Name::ParenLF)
-						| (Kind::Squaren, Name::SquarenLF)
-						| (Kind::Bracket, Name::BracketLF) => {
+						if !last_token_was_comma {
 							tokens.push(t);
-							last_token_was_operator = true;
-							last_token_was_comma = false;
+							last_token_was_operator = false;
+							last_token_was_comma = true;
 							last_token_was_newline = false;
 						}
-						//
-						(Kind::Paren, Name::ParenRT)
-						| (Kind::Squaren, Name::SquarenRT)
-						| (Kind::Bracket, Name::BracketRT) => {
-							if last_token_was_newline {
-								tokens.pop();
-							}
+					}
+					//
+					(Kind::Stop, Name::Newline) => {
+						if !last_token_was_operator
+							&& !last_token_was_comma && !last_token_was_newline
+							&& !skip_initial_newlines
+						{
 							tokens.push(t);
 							last_token_was_operator = false;
 							last_token_was_comma = false;
-							last_token_was_newline = false;
+							last_token_was_newline = true;
 						}
-						//
-						(Kind::Operator, _) => {
-							if last_token_was_newline {
-								tokens.pop();
-							}
-							tokens.push(t);
-							last_token_was_operator = true;
-							last_token_was_comma = false;
-							last_token_was_newline = false;
+						line += 1;
+					}
+					//
+					(Kind::Paren, Name::ParenLF)
+					| (Kind::Squaren, Name::SquarenLF)
+					| (Kind::Bracket, Name::BracketLF) => {
+						tokens.push(t);
+						last_token_was_operator = true;
+						last_token_was_comma = false;
+						last_token_was_newline = false;
+					}
+					//
+					(Kind::Paren, Name::ParenRT)
+					| (Kind::Squaren, Name::SquarenRT)
+					| (Kind::Bracket, Name::BracketRT) => {
+						if last_token_was_newline {
+							tokens.pop();
 						}
-						//
-						_ => {
-							tokens.push(t);
-
-							last_token_was_operator = false;
-							last_token_was_comma = false;
-							last_token_was_newline = false;
-							skip_initial_newlines = false;
+						tokens.push(t);
+						last_token_was_operator = false;
+						last_token_was_comma = false;
+						last_token_was_newline = false;
+					}
+					//
+					(Kind::Operator, _) => {
+						if last_token_was_newline {
+							tokens.pop();
 						}
+						tokens.push(t);
+						last_token_was_operator = true;
+						last_token_was_comma = false;
+						last_token_was_newline = false;
+					}
+					//
+					_ => {
+						tokens.push(t);
+
+						last_token_was_operator = false;
+						last_token_was_comma = false;
+						last_token_was_newline = false;
+						skip_initial_newlines = false;
 					}
-
-					cursor += mat.end();
-					continue 'outer;
 				}
-				None => {}
+
+				cursor += mat.end();
+				continue 'outer;
 			}
 		}
 	}
 	Ok(tokens)
 }
+
+// the select and the integer of a positional select, base.10
+fn position(tokens: &[Token], input: &str, line: u32) -> Option<Token> {
+	lazy_static! {
+		static ref SELECT: Regex = Regex::new(r"^\.[0-9]").unwrap();
+		static ref INTEGER: Regex = Regex::new(r"^[0-9']+").unwrap();
+	}
+	let names: Vec<Name> =
+		tokens.iter().rev().take(2).map(|t| t.of.name).collect();
+	let (kind, name, text) = match names.first() {
+		Some(Name::Ref | Name::ParenRT | Name::BracketRT)
+			if SELECT.is_match(input) =>
+		{
+			(Kind::Operator, Name::Select, ".")
+		}
+		// base.1.2
+		Some(Name::Integer)
+			if names.get(1) == Some(&Name::Select)
+				&& SELECT.is_match(input) =>
+		{
+			(Kind::Operator, Name::Select, ".")
+		}
+		Some(Name::Select) => {
+			let mat = INTEGER.find(input)?;
+			(Kind::Number, Name::Integer, mat.as_str())
+		}
+		_ => return None,
+	};
+	Some(Token {
+		of: Of { kind, name },
+		meta: Meta {
+			line,
+			text: text.to_string(),
+		},
+	})
+}
@@ -0,0 +1,768 @@
+/*
+transpiles a reduced program to c
+
+every point that isn't a constant is a static variable, rpl_update
+recomputes them in the order they were reduced, which is always after
+the points they depend on. inputs are left for the host to write
+
+an index outside its array wraps around and sets the overflow flag of
+its point, p_overflow, an index of an empty array is 0
+
+integers wrap like they do in the runtime. what the runtime raises as an
+error, a division by zero, an integer to a negative power or a match
+where no arm holds, is 0 in c and sets the error flag of its point,
+p_error
+
+an if whose branches need no work of their own is a branchless select,
+otherwise the points only one branch needs are computed inside that
+branch, so the branch not taken costs nothing, same as the runtime
+
+an operator over graphs is a loop over c arrays, the graphs it reads are
+arrays too, a point of a looped graph is read from its array, unless
+other points need it too, then it's computed on its own, a point only
+other loops read is never computed on its own
+
+a filtered array is an array as long as its source and a count of how
+much of it is filled
+
+a register and a latch are static too. the host fires a clock or an
+input a signal is computed from by setting its p_fired flag, rpl_update
+flips the phase of a fired clock, computes the points, writes every
+latch that fired to its register, then computes them again from the
+registers written
+*/
+
+use super::ir::{Ir, Type};
+use super::parser::IDX;
+use super::reducer::{operands, sources, Point};
+use super::tokenizer::Name;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct C<'a> {
+	code: &'a [Point],
+	types: &'a [Type],
+	owner: Vec<Option<(IDX, bool)>>, // the if branch a point belongs to
+	loops: BTreeMap<IDX, (Name, IDX, IDX)>, // graphs computed in a loop
+	arrays: BTreeSet<IDX>,           // graphs the loops read
+	cell: Vec<Option<(IDX, usize)>>, // the loop a point is in
+}
+
+pub fn transpiler(ir: &Ir) -> Result<String, String> {
+	let mut c = C {
+		code: &ir.code,
+		types: &ir.types,
+		owner: vec![None; ir.code.len()],
+		loops: BTreeMap::new(),
+		arrays: BTreeSet::new(),
+		cell: vec![None; ir.code.len()],
+	};
+	c.own();
+	c.loops(&ir.each);
+
+	let mut output = String::new();
+	output.push_str("#include <stdint.h>\n#include <math.h>\n\n");
+	output.push_str(HELPERS);
+	output.push_str(
+		"static inline int64_t rpl_wrap(int64_t i, int64_t n) {\n\tint64_t r = i % n;\n\treturn r < 0 ? r + n : r;\n}\n\n",
+	);
+
+	// the latches, and the clocks and inputs that fire them
+	let mut latches = Vec::new();
+	let mut fired = BTreeSet::new();
+	for (idx, point) in c.code.iter().enumerate() {
+		if let Point::Latch(r, s, _) = point {
+			let sources = sources(c.code, *s);
+			fired.extend(sources.iter().cloned());
+			latches.push((idx, *r, sources));
+		}
+	}
+
+	for (idx, point) in c.code.iter().enumerate() {
+		match point {
+			Point::Input(name) => output.push_str(&format!(
+				"{} p{}; // input\n",
+				input_type(*name),
+				idx
+			)),
+			Point::Clock(period) => output.push_str(&format!(
+				"static int64_t p{}; // clock {}\n",
+				idx, period
+			)),
+			Point::Register | Point::Latch(_, _, _) => output
+				.push_str(&format!("static {} p{};\n", c.ctype(idx), idx)),
+			Point::Graph(points)
+				if c.arrays.contains(&idx)
+					&& points.iter().all(|p| !variable(&c.code[*p])) =>
+			{
+				let values: Vec<String> =
+					points.iter().map(|p| c.operand(*p)).collect();
+				output.push_str(&format!(
+					"static const {} p{}[{}] = {{{}}};\n",
+					c.element_type(idx),
+					idx,
+					points.len(),
+					values.join(", ")
+				))
+			}
+			Point::Graph(points)
+				if c.arrays.contains(&idx)
+					|| c.loops.contains_key(&idx) =>
+			{
+				output.push_str(&format!(
+					"static {} p{}[{}];\n",
+					c.element_type(idx),
+					idx,
+					points.len()
+				));
+				output.push_str(&c.flag(idx));
+			}
+			Point::Filter(a, _) => output.push_str(&format!(
+				"static {} p{}[{}];\nstatic int64_t p{}_n;\n",
+				c.element_type(*a),
+				idx,
+				c.len(*a),
+				idx
+			)),
+			_ if c.cell[idx].is_some() => {}
+			Point::Index(_, _) => output.push_str(&format!(
+				"static {} p{};\nstatic uint8_t p{}_overflow;\n",
+				c.ctype(idx),
+				idx,
+				idx
+			)),
+			Point::Op1(_, _)
+			| Point::Op2(_, _, _)
+			| Point::If(_, _, _) => {
+				output.push_str(&format!(
+					"static {} p{};\n",
+					c.ctype(idx),
+					idx
+				));
+				output.push_str(&c.flag(idx));
+			}
+			Point::Tally(_) => output.push_str(&format!(
+				"static {} p{};\n",
+				c.ctype(idx),
+				idx
+			)),
+			_ => {}
+		}
+		if fired.contains(&idx) {
+			output.push_str(&format!("uint8_t p{}_fired;\n", idx));
+		}
+	}
+
+	output.push_str(match latches.is_empty() {
+		true => "\nvoid rpl_update(void) {\n",
+		false => "\nstatic void rpl_points(void) {\n",
+	});
+	// a flag is only set by what fails, a point in a branch not taken
+	// has no error left over from the update before
+	for idx in (0..c.code.len()).filter(|idx| c.fails(*idx)) {
+		output.push_str(&format!("\tp{}_error = 0;\n", idx));
+	}
+	for idx in 0..c.code.len() {
+		if c.owner[idx].is_none() {
+			output.push_str(&c.statement(idx, 1)?);
+		}
+	}
+	output.push_str("}\n");
+	if latches.is_empty() {
+		return Ok(output);
+	}
+
+	let flags = |sources: &BTreeSet<IDX>| {
+		sources
+			.iter()
+			.map(|idx| format!("p{}_fired", idx))
+			.collect::<Vec<String>>()
+			.join(" | ")
+	};
+	output.push_str("\nvoid rpl_update(void) {\n");
+	for idx in &fired {
+		if let Point::Clock(_) = c.code[*idx] {
+			output.push_str(&format!("\tp{} ^= p{}_fired;\n", idx, idx));
+		}
+	}
+	output.push_str(&format!(
+		"\trpl_points();\n\tif (!({})) return;\n",
+		flags(&fired)
+	));
+	for (idx, r, sources) in &latches {
+		output.push_str(&format!(
+			"\tif ({}) p{} = p{};\n",
+			flags(sources),
+			r,
+			idx
+		));
+	}
+	for idx in &fired {
+		output.push_str(&format!("\tp{}_fired = 0;\n", idx));
+	}
+	output.push_str("\trpl_points();\n}\n");
+	Ok(output)
+}
+
+impl C<'_> {
+	// a point that only one branch of an if uses belongs to that branch
+	fn own(&mut self) {
+		let uses = uses(self.code);
+		// inner ifs come before the ifs that use them, so they claim
+		// their own branches first
+		for idx in 0..self.code.len() {
+			if let Point::If(_, a, b) = self.code[idx] {
+				self.claim(a, (idx, true), &uses);
+				self.claim(b, (idx, false), &uses);
+			}
+		}
+	}
+
+	fn claim(&mut self, idx: IDX, owner: (IDX, bool), uses: &[usize]) {
+		if uses[idx] != 1
+			|| self.owner[idx].is_some()
+			|| !computed(&self.code[idx])
+		{
+			return;
+		}
+		self.owner[idx] = Some(owner);
+		match self.code[idx] {
+			// its branches are already its own
+			Point::If(c, _, _) => self.claim(c, owner, uses),
+			Point::Op2(_, a, b) => {
+				self.claim(a, owner, uses);
+				self.claim(b, owner, uses);
+			}
+			_ => {}
+		}
+	}
+
+	// an elementwise graph of single values, over arrays of single values
+	// or single values, is a loop
+	fn loops(&mut self, each: &BTreeMap<IDX, (Name, IDX, IDX)>) {
+		let mut uses = uses(self.code);
+		// later loops first, the points only their cells read are cells
+		// of the loops before them, not single values of their own
+		for (idx, (name, a, b)) in each.iter().rev() {
+			let folded = match &self.code[*idx] {
+				Point::Graph(points) => {
+					!points.iter().any(|p| computed(&self.code[*p]))
+				}
+				_ => true,
+			};
+			if folded
+				|| !(self.flat(*idx) && self.flat(*a) && self.flat(*b))
+			{
+				continue;
+			}
+			self.loops.insert(*idx, (*name, *a, *b));
+			if let Point::Graph(points) = &self.code[*idx] {
+				for (i, point) in points.iter().enumerate() {
+					// a point other points need too is computed on its own
+					if let Point::Op2(_, x, y) = self.code[*point] {
+						if uses[*point] == 1 {
+							self.cell[*point] = Some((*idx, i));
+							uses[x] -= 1;
+							uses[y] -= 1;
+						}
+					}
+				}
+			}
+		}
+		let filters = self.code.iter().filter_map(|point| match point {
+			Point::Filter(a, m) => Some((Name::Not, *a, *m)),
+			_ => None,
+		});
+		for (_, a, b) in self.loops.values().cloned().chain(filters) {
+			for operand in [&a, &b] {
+				if matches!(self.code[*operand], Point::Graph(_))
+					&& !self.loops.contains_key(operand)
+				{
+					self.arrays.insert(*operand);
+				}
+			}
+		}
+	}
+
+	// a single value, or a graph of single values
+	fn flat(&self, idx: IDX) -> bool {
+		match &self.code[idx] {
+			Point::Graph(points) => points
+				.iter()
+				.all(|p| !matches!(self.code[*p], Point::Graph(_))),
+			_ => true,
+		}
+	}
+
+	fn statement(&self, idx: IDX, depth: usize) -> Result<String, String> {
+		let tabs = "\t".repeat(depth);
+		if self.cell[idx].is_some() {
+			return Ok(String::new());
+		}
+		match &self.code[idx] {
+			Point::Graph(points) if self.loops.contains_key(&idx) => {
+				let (name, a, b) = self.loops[&idx];
+				let x = self.each_operand(a);
+				let y = self.each_operand(b);
+				let types = (self.element_type(a), self.element_type(b));
+				Ok(format!(
+					"{}for (int i = 0; i < {}; i++) p{}[i] = {};\n",
+					tabs,
+					points.len(),
+					idx,
+					self.expression(name, &x, &y, types, idx)?
+				))
+			}
+			Point::Graph(points)
+				if self.arrays.contains(&idx)
+					&& points.iter().any(|p| variable(&self.code[*p])) =>
+			{
+				let mut output = String::new();
+				for (i, point) in points.iter().enumerate() {
+					output.push_str(&format!(
+						"{}p{}[{}] = {};\n",
+						tabs,
+						idx,
+						i,
+						self.operand(*point)
+					));
+				}
+				Ok(output)
+			}
+			Point::Op2(name, a, b) => Ok(format!(
+				"{}p{} = {};\n",
+				tabs,
+				idx,
+				self.op2(*name, *a, *b, idx)?
+			)),
+			Point::Op1(name, a) => Ok(format!(
+				"{}p{} = {};\n",
+				tabs,
+				idx,
+				self.op1(*name, *a, idx)?
+			)),
+			// branchless, every point is written and the count only moves
+			// past the ones the mask keeps
+			Point::Filter(a, m) => Ok(format!(
+				"{}p{}_n = 0;\n{}for (int i = 0; i < {}; i++) {{\n{}\tp{}[p{}_n] = {};\n{}\tp{}_n += ({}) != 0;\n{}}}\n",
+				tabs,
+				idx,
+				tabs,
+				self.len(*a),
+				tabs,
+				idx,
+				idx,
+				self.each_operand(*a),
+				tabs,
+				idx,
+				self.each_operand(*m),
+				tabs
+			)),
+			Point::Tally(a) => {
+				Ok(format!("{}p{} = p{}_n;\n", tabs, idx, a))
+			}
+			Point::Latch(_, _, v) => Ok(format!(
+				"{}p{} = {};\n",
+				tabs,
+				idx,
+				self.operand(*v)
+			)),
+			Point::Index(a, i) if matches!(self.code[*a], Point::Filter(_, _)) => {
+				let i = self.operand(*i);
+				let n = format!("p{}_n", a);
+				Ok(format!(
+					"{}p{}_overflow = {} < 0 || {} >= {};\n{}p{} = {} ? p{}[rpl_wrap({}, {})] : 0;\n",
+					tabs, idx, i, i, n, tabs, idx, n, a, i, n
+				))
+			}
+			Point::Index(a, i) => {
+				let points = match &self.code[*a] {
+					Point::Graph(points) => points,
+					_ => {
+						return Err(
+							"only arrays can be indexed".to_string()
+						)
+					}
+				};
+				if points.is_empty() {
+					return Ok(format!("{}p{} = 0;\n", tabs, idx));
+				}
+				let elements: Vec<String> =
+					points.iter().map(|p| self.operand(*p)).collect();
+				let i = self.operand(*i);
+				Ok(format!(
+					"{}p{}_overflow = {} < 0 || {} >= {};\n{}p{} = (({}[]){{{}}})[rpl_wrap({}, {})];\n",
+					tabs,
+					idx,
+					i,
+					i,
+					points.len(),
+					tabs,
+					idx,
+					self.ctype(idx),
+					elements.join(", "),
+					i,
+					points.len()
+				))
+			}
+			Point::If(_, a, _)
+				if matches!(self.code[*a], Point::Graph(_)) =>
+			{
+				Err("an if of graphs can't be transpiled to c".to_string())
+			}
+			Point::If(c, a, b) => {
+				let then = self.branch(idx, true, depth + 1)?;
+				let other = self.branch(idx, false, depth + 1)?;
+				// the last arm of a match that may not hold
+				let flag = match self.fails(idx) {
+					true => format!(
+						"{}if (!({})) p{}_error = 1;\n",
+						tabs,
+						self.operand(*c),
+						idx
+					),
+					false => String::new(),
+				};
+				if then.is_empty() && other.is_empty() {
+					Ok(format!(
+						"{}{}p{} = {};\n",
+						flag,
+						tabs,
+						idx,
+						self.select(idx, *c, *a, *b)
+					))
+				} else {
+					Ok(format!(
+						"{}{}if ({}) {{\n{}{}\tp{} = {};\n{}}} else {{\n{}{}\tp{} = {};\n{}}}\n",
+						flag,
+						tabs,
+						self.operand(*c),
+						then,
+						tabs,
+						idx,
+						self.operand(*a),
+						tabs,
+						other,
+						tabs,
+						idx,
+						self.operand(*b),
+						tabs,
+					))
+				}
+			}
+			_ => Ok(String::new()),
+		}
+	}
+
+	fn branch(
+		&self,
+		idx: IDX,
+		taken: bool,
+		depth: usize,
+	) -> Result<String, String> {
+		let mut output = String::new();
+		for point in 0..idx {
+			if self.owner[point] == Some((idx, taken)) {
+				output.push_str(&self.statement(point, depth)?);
+			}
+		}
+		Ok(output)
+	}
+
+	// integers select through a mask, c compilers already turn a ternary
+	// of doubles into a select
+	fn select(&self, idx: IDX, c: IDX, a: IDX, b: IDX) -> String {
+		let (c, a, b) =
+			(self.operand(c), self.operand(a), self.operand(b));
+		match self.ctype(idx) {
+			"int64_t" => format!(
+				"{} ^ (({} ^ {}) & -(int64_t)(({}) != 0))",
+				b, a, b, c
+			),
+			_ => format!("({}) ? {} : {}", c, a, b),
+		}
+	}
+
+	// integers are negated as uint64_t, so the most negative one wraps
+	// to itself as it does in the runtime
+	fn op1(&self, name: Name, a: IDX, idx: IDX) -> Result<String, String> {
+		let x = self.operand(a);
+		let integer = self.ctype(a) == "int64_t";
+		match name {
+			Name::Not => Ok(format!("~{}", x)),
+			Name::Add if integer => Ok(format!(
+				"({} < 0 ? (int64_t)(0 - (uint64_t){}) : {})",
+				x, x, x
+			)),
+			Name::Add => Ok(format!("fabs({})", x)),
+			Name::Sub if integer => {
+				Ok(format!("(int64_t)(0 - (uint64_t){})", x))
+			}
+			Name::Sub => Ok(format!("-{}", x)),
+			Name::Div if integer => {
+				Ok(format!("rpl_inv({}, &p{}_error)", x, idx))
+			}
+			Name::Div => Ok(format!("1.0 / {}", x)),
+			_ => Err(format!("{:?} can't be transpiled to c", name)),
+		}
+	}
+
+	// a point of integers that divides or raises to a power may fail,
+	// and has an error flag to say so
+	fn fails(&self, idx: IDX) -> bool {
+		if self.cell[idx].is_some() {
+			return false;
+		}
+		match self.code[idx] {
+			Point::Op2(Name::Div | Name::Exp, a, b) => {
+				self.ctype(a) == "int64_t" && self.ctype(b) == "int64_t"
+			}
+			Point::Op1(Name::Div, a) => self.ctype(a) == "int64_t",
+			Point::If(_, _, b) => matches!(self.code[b], Point::Error(_)),
+			Point::Graph(_) => match self.loops.get(&idx) {
+				Some((Name::Div | Name::Exp, a, b)) => {
+					self.element_type(*a) == "int64_t"
+						&& self.element_type(*b) == "int64_t"
+				}
+				_ => false,
+			},
+			_ => false,
+		}
+	}
+
+	fn flag(&self, idx: IDX) -> String {
+		match self.fails(idx) {
+			true => format!("static uint8_t p{}_error;\n", idx),
+			false => String::new(),
+		}
+	}
+
+	fn len(&self, idx: IDX) -> usize {
+		match &self.code[idx] {
+			Point::Graph(points) => points.len(),
+			_ => 1,
+		}
+	}
+
+	fn op2(
+		&self,
+		name: Name,
+		a: IDX,
+		b: IDX,
+		idx: IDX,
+	) -> Result<String, String> {
+		let (x, y) = (self.operand(a), self.operand(b));
+		self.expression(name, &x, &y, (self.ctype(a), self.ctype(b)), idx)
+	}
+
+	// integers wrap as they do in the runtime, they're added and
+	// multiplied as uint64_t, a division by zero or a negative power is
+	// 0 and sets the error flag of idx
+	fn expression(
+		&self,
+		name: Name,
+		x: &str,
+		y: &str,
+		types: (&str, &str),
+		idx: IDX,
+	) -> Result<String, String> {
+		let integers = types == ("int64_t", "int64_t");
+		let op = match name {
+			Name::Add | Name::Sub | Name::Mul if integers => {
+				let op = match name {
+					Name::Add => "+",
+					Name::Sub => "-",
+					_ => "*",
+				};
+				return Ok(format!(
+					"(int64_t)((uint64_t){} {} (uint64_t){})",
+					x, op, y
+				));
+			}
+			Name::Div if integers => {
+				return Ok(format!(
+					"rpl_div({}, {}, &p{}_error)",
+					x, y, idx
+				))
+			}
+			Name::Add => "+",
+			Name::Sub => "-",
+			Name::Mul => "*",
+			Name::Div => "/",
+			Name::And => "&",
+			Name::Or => "|",
+			Name::Eq => "==",
+			Name::Ne => "!=",
+			Name::Gt => ">",
+			Name::Lt => "<",
+			Name::Ge => ">=",
+			Name::Le => "<=",
+			Name::Exp if integers => {
+				return Ok(format!(
+					"rpl_pow({}, {}, &p{}_error)",
+					x, y, idx
+				))
+			}
+			Name::Exp => return Ok(format!("pow({}, {})", x, y)),
+			_ => {
+				return Err(format!("{:?} can't be transpiled to c", name))
+			}
+		};
+		Ok(format!("{} {} {}", x, op, y))
+	}
+
+	// the ith point of an array a loop reads, or a single value
+	fn each_operand(&self, idx: IDX) -> String {
+		match &self.code[idx] {
+			Point::Graph(_) => format!("p{}[i]", idx),
+			_ => self.operand(idx),
+		}
+	}
+
+	// the type of the points of an array, double if any is
+	fn element_type(&self, idx: IDX) -> &'static str {
+		match &self.code[idx] {
+			Point::Graph(points)
+				if points.iter().any(|p| self.ctype(*p) == "double") =>
+			{
+				"double"
+			}
+			Point::Graph(_) => "int64_t",
+			_ => self.ctype(idx),
+		}
+	}
+
+	// constants are written in place
+	fn operand(&self, idx: IDX) -> String {
+		if let Some((array, i)) = self.cell[idx] {
+			return format!("p{}[{}]", array, i);
+		}
+		match &self.code[idx] {
+			Point::Integer(v) => format!("{}", v),
+			Point::Decimal(v) => format!("{:?}", v),
+			Point::Error(e) => format!("0 /* {} */", e),
+			Point::Sequence(n) => format!("0 /* {}, {}, .. */", n, n + 1),
+			_ => format!("p{}", idx),
+		}
+	}
+
+	fn ctype(&self, idx: IDX) -> &'static str {
+		match &self.code[idx] {
+			Point::Decimal(_) => "double",
+			Point::Input(Name::F32 | Name::F64 | Name::F128) => "double",
+			Point::Op2(name, a, b) => match name {
+				Name::Eq
+				| Name::Ne
+				| Name::Gt
+				| Name::Lt
+				| Name::Ge
+				| Name::Le => "int64_t",
+				_ => match (self.ctype(*a), self.ctype(*b)) {
+					("int64_t", "int64_t") => "int64_t",
+					_ => "double",
+				},
+			},
+			Point::Op1(Name::Div, _) => "double",
+			Point::Op1(Name::Not, _) => "int64_t",
+			Point::Op1(_, a) => match self.ctype(*a) {
+				"double" => "double",
+				_ => "int64_t",
+			},
+			Point::If(_, a, _) | Point::Latch(_, _, a) => self.ctype(*a),
+			Point::Register => match self.types[idx] {
+				Type::Decimal => "double",
+				_ => "int64_t",
+			},
+			Point::Index(a, _) => match &self.code[*a] {
+				Point::Graph(points) if !points.is_empty() => {
+					self.ctype(points[0])
+				}
+				Point::Filter(a, _) => self.element_type(*a),
+				_ => "int64_t",
+			},
+			_ => "int64_t",
+		}
+	}
+}
+
+// integers wrap and a power is squared and multiplied, as the runtime's
+// wrapping_pow, what the runtime raises as an error sets the error flag
+const HELPERS: &str =
+	"static inline int64_t rpl_div(int64_t a, int64_t b, uint8_t *error) {
+	if (b == 0) {
+		*error = 1;
+		return 0;
+	}
+	return b == -1 ? (int64_t)(0 - (uint64_t)a) : a / b;
+}
+
+static inline double rpl_inv(int64_t a, uint8_t *error) {
+	if (a == 0) {
+		*error = 1;
+		return 0.0;
+	}
+	return 1.0 / a;
+}
+
+static inline int64_t rpl_pow(int64_t a, int64_t b, uint8_t *error) {
+	uint64_t c = 1, x = (uint64_t)a;
+	uint32_t n = (uint32_t)b;
+	if (b < 0) {
+		*error = 1;
+		return 0;
+	}
+	for (; n; n >>= 1, x *= x) {
+		if (n & 1) c *= x;
+	}
+	return (int64_t)c;
+}
+
+";
+
+fn computed(point: &Point) -> bool {
+	matches!(
+		point,
+		Point::Op1(_, _)
+			| Point::Op2(_, _, _)
+			| Point::If(_, _, _)
+			| Point::Index(_, _)
+			| Point::Filter(_, _)
+			| Point::Tally(_)
+			| Point::Latch(_, _, _)
+	)
+}
+
+// a point whose value changes while running, an array of them is copied
+// every update rather than written once
+fn variable(point: &Point) -> bool {
+	computed(point)
+		|| matches!(
+			point,
+			Point::Input(_) | Point::Clock(_) | Point::Register
+		)
+}
+
+// how many points need each point
+fn uses(code: &[Point]) -> Vec<usize> {
+	let mut uses = vec![0; code.len()];
+	for point in code {
+		for idx in operands(point) {
+			uses[idx] += 1;
+		}
+	}
+	uses
+}
+
+fn input_type(name: Name) -> &'static str {
+	match name {
+		Name::I8 => "int8_t",
+		Name::I16 => "int16_t",
+		Name::I32 => "int32_t",
+		Name::U8 => "uint8_t",
+		Name::U16 => "uint16_t",
+		Name::U32 => "uint32_t",
+		Name::U64 => "uint64_t",
+		Name::F32 => "float",
+		Name::F64 | Name::F128 => "double",
+		_ => "int64_t",
+	}
+}
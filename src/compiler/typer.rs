@@ -1,16 +1,562 @@
-use super::expander::Maps;
-use super::parser;
+/*
+structural typing, two graphs have the same type when they have the same
+labels, and the points under those labels have the same types, a point
+without a label is labelled by its place, {1, 2} is {0: 1, 1: 2}
+
+an array the typer can't count, a range or a filter, is an Array of the
+type its points share, it has the type of any graph of points of that type
+
+a graph with a return point is function-like, its type is the type of
+what it returns
+
+booleans are integers, comparisons are 1 or 0
+
+a match is the type all its arms share, a match without an arm that
+always holds is a warning, not an error, it's an error when it's run
+and no arm holds
+*/
+
+use super::expander::{find, is_arm, Maps};
+use super::parser::{Ast, EID};
 use super::tokenizer::Name;
-// use std::collections::BTreeMap;
+use std::collections::BTreeMap;
+
+pub type Typs = Vec<BTreeMap<String, Typ>>;
 
-pub type Fast = Vec<Ast>;
-pub type AstIndex = usize;
+#[derive(Debug, Clone, PartialEq)]
+pub enum Typ {
+	Unknown, // not typed yet, unifies with anything
+	Integer,
+	Decimal,
+	String,
+	Clock,
+	Graph(BTreeMap<String, Typ>),
+	Array(Box<Typ>), // as many points of one type as it turns out to have
+	Sequence,        // unbounded Integers, only sized or indexed
+}
+
+pub struct Types<'a> {
+	envs: &'a Maps,
+	typs: Typs,
+	label: String, // the labelled point being typed
+	pub warnings: Vec<String>,
+}
 
 pub fn typer(
-	ast: &parser::Ast,
-	envs: Maps,
-) -> Result<(Fast, Maps), String> {
-	// let mut parse = Parse { envs: envs.clone() };
-	// parse.reduce(ast, 0)
-	Ok((vec![Ast::Nothing], envs))
+	ast: &Ast,
+	envs: &Maps,
+) -> Result<(Typs, Vec<String>), String> {
+	let mut types = Types {
+		envs,
+		typs: vec![BTreeMap::new(); envs.len()],
+		label: "the program".to_string(),
+		warnings: Vec::new(),
+	};
+	types.typ(ast, 0)?;
+	Ok((types.typs, types.warnings))
+}
+
+impl Types<'_> {
+	fn typ(&mut self, ast: &Ast, parent: EID) -> Result<Typ, String> {
+		match ast {
+			Ast::Graph(eid, points) if points.iter().any(is_arm) => {
+				self.arms(points, *eid)
+			}
+
+			// a point of a graph has a value, a sequence never ends to be one
+			Ast::Graph(eid, points) => {
+				let mut labels = BTreeMap::new();
+				for (i, point) in points.iter().enumerate() {
+					let typ = self.typ(point, *eid)?;
+					let label = match point {
+						Ast::Key(label, _) => label.clone(),
+						_ => i.to_string(),
+					};
+					if typ == Typ::Sequence {
+						let what = match point {
+							Ast::Key(_, _) => format!("{} is", label),
+							_ => format!(
+								"in {}, point {} is",
+								self.label, i
+							),
+						};
+						return Err(format!(
+							"TypeError: {}",
+							unbounded(&what)
+						));
+					}
+					labels.insert(label, typ);
+				}
+				match labels.remove("<return>") {
+					Some(typ) => Ok(typ),
+					None => Ok(Typ::Graph(labels)),
+				}
+			}
+
+			Ast::Key(label, point) => {
+				if let Some(typ) = self.typs[parent].get(label) {
+					return Ok(typ.clone());
+				}
+				// claim the label first, points that refer to themselves
+				// through a signal don't know their own type yet
+				self.typs[parent].insert(label.clone(), Typ::Unknown);
+				let outer =
+					std::mem::replace(&mut self.label, label.clone());
+				let mut typ = self.typ(point, parent)?;
+				// a register is 0 until a value is latched into it, then
+				// it's the type of what's latched, which may read it
+				if let Ast::Op2(Name::Signal, _, _) = &**point {
+					let mut claim = Typ::Integer;
+					for _ in 0..3 {
+						if typ == claim {
+							break;
+						}
+						claim = match typ {
+							Typ::Unknown => Typ::Integer,
+							_ => typ,
+						};
+						self.typs[parent]
+							.insert(label.clone(), claim.clone());
+						typ = self.typ(point, parent)?;
+					}
+				}
+				self.label = outer;
+				self.typs[parent].insert(label.clone(), typ.clone());
+				Ok(typ)
+			}
+
+			Ast::Ref(eid, label) => {
+				let (eid, point) = find(self.envs, label, *eid)?;
+				match point {
+					Ast::Ref(_, _) => self.typ(point, eid),
+					_ => {
+						let key = Ast::Key(
+							label.clone(),
+							Box::new(point.clone()),
+						);
+						self.typ(&key, eid)
+					}
+				}
+			}
+
+			Ast::If(cond, then, other) => {
+				match self.typ(cond, parent)? {
+					Typ::Integer | Typ::Unknown => {}
+					x => {
+						return Err(format!(
+							"TypeError: condition of if is {:?}, it must be an Integer",
+							x
+						))
+					}
+				}
+				let then = self.typ(then, parent)?;
+				let other = self.typ(other, parent)?;
+				if then == Typ::Sequence || other == Typ::Sequence {
+					return Err(format!(
+						"TypeError: in {}, {}",
+						self.label,
+						unbounded("a branch of if is")
+					));
+				}
+				unify(&then, &other).ok_or_else(|| {
+					format!(
+						"TypeError: branches of if are {:?} and {:?}, they must have the same type",
+						then, other
+					)
+				})
+			}
+
+			// a range selects an array, a single index selects an element
+			Ast::Op2(Name::Index, array, selector)
+				if matches!(**selector, Ast::Range(_, _, _, _)) =>
+			{
+				match self.typ(array, parent)? {
+					Typ::Sequence => {
+						Ok(Typ::Array(Box::new(Typ::Integer)))
+					}
+					typ => Ok(Typ::Array(Box::new(element(&typ)))),
+				}
+			}
+
+			Ast::Op2(Name::Index, array, index) => {
+				match self.typ(index, parent)? {
+					Typ::Integer | Typ::Unknown => {}
+					x => {
+						return Err(format!(
+							"TypeError: index in {} is {:?}, it must be an Integer",
+							self.label, x
+						))
+					}
+				}
+				match self.typ(array, parent)? {
+					Typ::Sequence => Ok(Typ::Integer),
+					typ => Ok(element(&typ)),
+				}
+			}
+
+			// a shape is an array of Integers
+			Ast::Op1(Name::Shape, point) => {
+				match self.typ(point, parent)? {
+					Typ::Sequence => Err(self.unbounded(Name::Shape)),
+					_ => Ok(Typ::Array(Box::new(Typ::Integer))),
+				}
+			}
+
+			// x $ [2 3] is the values of x in rows, as deep as it has
+			// dimensions, when they aren't counted here its rows aren't typed
+			Ast::Op2(Name::Shape, point, dims) => {
+				let typ = self.typ(point, parent)?;
+				if typ == Typ::Sequence {
+					return Err(self.unbounded(Name::Shape));
+				}
+				self.typ(dims, parent)?;
+				let depth = match &**dims {
+					Ast::Space(sizes) => sizes
+						.iter()
+						.filter(|size| !matches!(size, Ast::Size(_)))
+						.count(),
+					Ast::Graph(_, points) => points.len(),
+					_ => 0,
+				};
+				let mut typ = match depth {
+					0 => Typ::Unknown,
+					_ => leaf(&typ),
+				};
+				for _ in 0..depth.max(1) {
+					typ = Typ::Array(Box::new(typ));
+				}
+				Ok(typ)
+			}
+
+			// `x flips the bits of integers, +x and -x keep the type of x,
+			// /x is a decimal, even of an integer, /2 is 0.5
+			Ast::Op1(
+				name @ (Name::Not | Name::Add | Name::Sub | Name::Div),
+				point,
+			) => {
+				let typ = self.typ(point, parent)?;
+				if typ == Typ::Sequence {
+					return Err(self.unbounded(*name));
+				}
+				unary(*name, &typ).ok_or_else(|| match name {
+					Name::Not => format!(
+						"TypeError: in {}, ` of {:?}, only integers have bits to flip",
+						self.label, typ
+					),
+					_ => format!(
+						"TypeError: in {}, {:?} of {:?}, only numbers have one",
+						self.label, name, typ
+					),
+				})
+			}
+
+			// #x counts, a filtered array is a shorter array
+			Ast::Op1(Name::Index, _) => Ok(Typ::Integer),
+			Ast::Op2(Name::Not, array, mask) => {
+				let array = self.typ(array, parent)?;
+				self.typ(mask, parent)?;
+				match array {
+					Typ::Graph(_) | Typ::Array(_) | Typ::Unknown => {
+						Ok(Typ::Array(Box::new(element(&array))))
+					}
+					x => Err(format!(
+						"TypeError: in {}, {:?} is filtered, only arrays are",
+						self.label, x
+					)),
+				}
+			}
+
+			// (<7) is an array, (>7) never ends
+			Ast::Range(_, _, _, hi) => match **hi {
+				Ast::Nothing => Ok(Typ::Sequence),
+				_ => Ok(Typ::Array(Box::new(Typ::Integer))),
+			},
+
+			Ast::Op2(name, left, right) => {
+				let left = self.typ(left, parent)?;
+				let right = self.typ(right, parent)?;
+				match name {
+					// a gated point is its value, the signal only says when
+					Name::Signal => Ok(right),
+					// a sized sequence is as many of it as fit
+					Name::Sizer
+						if left == Typ::Sequence || right == Typ::Sequence =>
+					{
+						Ok(Typ::Array(Box::new(Typ::Integer)))
+					}
+					_ if left == Typ::Sequence || right == Typ::Sequence => {
+						Err(self.unbounded(*name))
+					}
+					Name::Sizer => match (left, right) {
+						(Typ::Unknown, x) | (x, _) => Ok(x),
+					},
+					// labels of b replace the same labels of a, the points
+					// of b without one go after the points of a
+					Name::Bleed => match (left, right) {
+						(Typ::Graph(mut a), Typ::Graph(b)) => {
+							let n = a.len();
+							for (label, typ) in b {
+								match label.parse::<usize>() {
+									Ok(i) => a.insert((n + i).to_string(), typ),
+									Err(_) => a.insert(label, typ),
+								};
+							}
+							Ok(Typ::Graph(a))
+						}
+						(a @ (Typ::Graph(_) | Typ::Array(_)), b @ (Typ::Graph(_) | Typ::Array(_))) => {
+							unify(&element(&a), &element(&b))
+								.map(|typ| Typ::Array(Box::new(typ)))
+								.ok_or_else(|| format!(
+									"TypeError: in {}, {:?} .. {:?} can't be joined, their points don't have the same type",
+									self.label, a, b
+								))
+						}
+						(Typ::Unknown, _) | (_, Typ::Unknown) => {
+							Ok(Typ::Unknown)
+						}
+						(a, b) => Err(format!(
+							"TypeError: in {}, {:?} .. {:?} can't be joined, only graphs can",
+							self.label, a, b
+						)),
+					},
+					_ => each(*name, &left, &right).ok_or_else(|| {
+						format!(
+							"TypeError: in {}, {:?} of {:?} and {:?}, graphs must have the same labels",
+							self.label, name, left, right
+						)
+					}),
+				}
+			}
+
+			// [3 4 i32] is rows of i32s, as deep as it has dimensions
+			Ast::Space(sizes) => match sizes.last() {
+				Some(Ast::Size(name)) => {
+					let mut typ = size(*name);
+					for _ in 1..sizes.len() {
+						typ = Typ::Array(Box::new(typ));
+					}
+					Ok(typ)
+				}
+				_ => Ok(Typ::Unknown),
+			},
+
+			Ast::Integer(_) => Ok(Typ::Integer),
+			Ast::Decimal(_) => Ok(Typ::Decimal),
+			Ast::String(_) => Ok(Typ::String),
+			Ast::Clock(_, _) => Ok(Typ::Clock),
+			_ => Ok(Typ::Unknown),
+		}
+	}
+}
+
+impl Types<'_> {
+	fn unbounded(&self, name: Name) -> String {
+		format!(
+			"TypeError: in {}, {}",
+			self.label,
+			unbounded(&format!("{:?} of", name))
+		)
+	}
+
+	fn arms(&mut self, arms: &[Ast], eid: EID) -> Result<Typ, String> {
+		let mut typ = Typ::Unknown;
+		let mut exhaustive = false;
+		for arm in arms {
+			let (guard, value) = match arm {
+				Ast::Op2(Name::Arrow, guard, value) => (guard, value),
+				x => return Err(format!("{:?} is not an arm", x)),
+			};
+			if exhaustive {
+				self.warnings.push(format!(
+					"Warning: in {}, an arm after one that always holds is never reached",
+					self.label
+				));
+			}
+			match self.typ(guard, eid)? {
+				Typ::Integer | Typ::Unknown => {}
+				x => {
+					return Err(format!(
+						"TypeError: guard of an arm in {} is {:?}, it must be an Integer",
+						self.label, x
+					))
+				}
+			}
+			exhaustive |= holds(guard);
+			let value = self.typ(value, eid)?;
+			typ = unify(&typ, &value).ok_or_else(|| {
+				format!(
+					"TypeError: arms of the match in {} are {:?} and {:?}, they must have the same type",
+					self.label, typ, value
+				)
+			})?;
+		}
+		if !exhaustive {
+			self.warnings.push(format!(
+				"Warning: the match in {} may not be exhaustive, no arm always holds, end it with an arm like _ -> value",
+				self.label
+			));
+		}
+		Ok(typ)
+	}
+}
+
+// a guard that holds whatever the inputs are
+fn holds(guard: &Ast) -> bool {
+	match guard {
+		Ast::Nothing => true,
+		Ast::Integer(v) => {
+			v.chars().any(|c| c.is_ascii_digit() && c != '0')
+		}
+		_ => false,
+	}
+}
+
+// what can't be done with a sequence, it's only sized or indexed
+//	Add of an unbounded sequence, it never ends, size it like ..
+pub fn unbounded(what: &str) -> String {
+	format!(
+		"{} an unbounded sequence, it never ends, size it like [10]! (>7) or index it like (>7)#[< 10]",
+		what
+	)
+}
+
+pub fn size(name: Name) -> Typ {
+	match name {
+		Name::F32 | Name::F64 | Name::F128 => Typ::Decimal,
+		Name::C8 | Name::C16 | Name::C32 => Typ::String,
+		_ => Typ::Integer,
+	}
+}
+
+// operators map over graphs, label by label
+fn each(name: Name, left: &Typ, right: &Typ) -> Option<Typ> {
+	match (left, right) {
+		(Typ::Graph(a), Typ::Graph(b)) => {
+			if a.len() != b.len() {
+				return None;
+			}
+			let mut labels = BTreeMap::new();
+			for (label, a) in a {
+				labels
+					.insert(label.clone(), each(name, a, b.get(label)?)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(Typ::Array(a), Typ::Array(b)) => {
+			Some(Typ::Array(Box::new(each(name, a, b)?)))
+		}
+		(Typ::Array(a), Typ::Graph(b))
+		| (Typ::Graph(b), Typ::Array(a)) => {
+			let mut labels = BTreeMap::new();
+			for (label, b) in b {
+				labels.insert(label.clone(), each(name, a, b)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(Typ::Graph(a), x) | (x, Typ::Graph(a)) => {
+			let mut labels = BTreeMap::new();
+			for (label, a) in a {
+				labels.insert(label.clone(), each(name, a, x)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(Typ::Array(a), x) | (x, Typ::Array(a)) => {
+			Some(Typ::Array(Box::new(each(name, a, x)?)))
+		}
+		_ => match name {
+			Name::Eq
+			| Name::Ne
+			| Name::Gt
+			| Name::Lt
+			| Name::Ge
+			| Name::Le => Some(Typ::Integer),
+			_ => Some(arithmetic(left, right)),
+		},
+	}
+}
+
+// unary operators map over graphs too
+fn unary(name: Name, typ: &Typ) -> Option<Typ> {
+	match (name, typ) {
+		(_, Typ::Graph(a)) => {
+			let mut labels = BTreeMap::new();
+			for (label, a) in a {
+				labels.insert(label.clone(), unary(name, a)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(_, Typ::Array(a)) => Some(Typ::Array(Box::new(unary(name, a)?))),
+		(Name::Not, Typ::Integer | Typ::Unknown) => Some(typ.clone()),
+		(Name::Not, _) => None,
+		(Name::Div, Typ::Integer | Typ::Decimal | Typ::Unknown) => {
+			Some(Typ::Decimal)
+		}
+		(_, Typ::Integer | Typ::Decimal | Typ::Unknown) => {
+			Some(typ.clone())
+		}
+		_ => None,
+	}
+}
+
+fn arithmetic(left: &Typ, right: &Typ) -> Typ {
+	match (left, right) {
+		(Typ::Integer, Typ::Integer) => Typ::Integer,
+		(Typ::Decimal, Typ::Integer)
+		| (Typ::Integer, Typ::Decimal)
+		| (Typ::Decimal, Typ::Decimal) => Typ::Decimal,
+		_ => Typ::Unknown,
+	}
+}
+
+// the type both a and b have, if they have one
+pub fn unify(a: &Typ, b: &Typ) -> Option<Typ> {
+	match (a, b) {
+		(Typ::Unknown, x) | (x, Typ::Unknown) => Some(x.clone()),
+		(Typ::Graph(a), Typ::Graph(b)) => {
+			if a.len() != b.len() {
+				return None;
+			}
+			let mut labels = BTreeMap::new();
+			for (label, a) in a {
+				labels.insert(label.clone(), unify(a, b.get(label)?)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(Typ::Array(a), Typ::Array(b)) => {
+			Some(Typ::Array(Box::new(unify(a, b)?)))
+		}
+		(Typ::Array(a), Typ::Graph(b))
+		| (Typ::Graph(b), Typ::Array(a)) => {
+			let mut labels = BTreeMap::new();
+			for (label, b) in b {
+				labels.insert(label.clone(), unify(a, b)?);
+			}
+			Some(Typ::Graph(labels))
+		}
+		(a, b) if a == b => Some(a.clone()),
+		_ => None,
+	}
+}
+
+// the type of the values of an array, however deep they are
+fn leaf(typ: &Typ) -> Typ {
+	match typ {
+		Typ::Graph(_) | Typ::Array(_) => leaf(&element(typ)),
+		_ => typ.clone(),
+	}
+}
+
+// the type the points of an array share, Unknown if they don't
+fn element(typ: &Typ) -> Typ {
+	match typ {
+		Typ::Graph(labels) => {
+			let mut typ = Typ::Unknown;
+			for value in labels.values() {
+				typ = unify(&typ, value).unwrap_or(Typ::Unknown);
+			}
+			typ
+		}
+		Typ::Array(typ) => (**typ).clone(),
+		_ => Typ::Unknown,
+	}
 }
@@ -4,7 +4,12 @@ use std::env;
 use std::fs;
 
 pub mod compiler;
-use compiler::{expander, parser, reducer, tokenizer};
+mod repl;
+use compiler::{
+    dot, eliminator, expander, ir, parser, reducer, runtime, tokenizer,
+    transpiler, typer,
+};
+use expander::Strategy;
 use parser::Ast;
 use tokenizer::Token;
 
@@ -14,15 +19,76 @@ use tokenizer::Token;
 fn main() {
     let args: Vec<String> = env::args().skip(1).collect();
 
-    if args.len() < 1 {
-        eprintln!("Usage: rpl.exe <source>");
+    let usage = "Usage: rpl.exe <source> [--expand full|selective] [--keep root.a,root.b]\n       rpl.exe dump --format dot <source>\n       rpl.exe repl [--expand full|selective]";
+    if args.is_empty() {
+        eprintln!("{}", usage);
         std::process::exit(1);
     }
 
+    // variable graphs are shared unless asked to expand everything
+    let strategy = match args.iter().position(|arg| arg == "--expand") {
+        None => Strategy::Selective,
+        Some(i) => match args.get(i + 1).map(|arg| arg.as_str()) {
+            Some("full") => Strategy::Full,
+            Some("selective") => Strategy::Selective,
+            x => {
+                eprintln!(
+                    "Error: --expand is full or selective, not {:?}",
+                    x
+                );
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // points pinned by path, --keep root.a,root.b or --keep root.a --keep root.b
+    let keep: Vec<String> = args
+        .iter()
+        .zip(args.iter().skip(1))
+        .filter(|(flag, _)| *flag == "--keep")
+        .flat_map(|(_, paths)| paths.split(','))
+        .map(|path| path.to_string())
+        .collect();
+
+    // the arguments that aren't flags or the values of flags
+    let words: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, arg)| {
+            !arg.starts_with("--")
+                && (*i == 0 || !args[i - 1].starts_with("--"))
+        })
+        .map(|(_, arg)| arg)
+        .collect();
+
     // read_file(&args[0]); // first run
     // compiler(&args[0], &args[1]);
-    let source = &args[0];
-    event_router(notify::op::WRITE, source);
+    match words[..] {
+        [command, source] if command == "dump" => {
+            let format = match args
+                .iter()
+                .position(|arg| arg == "--format")
+            {
+                Some(i) => args.get(i + 1).map_or("", |arg| arg.as_str()),
+                None => "dot",
+            };
+            match dump(source, format, strategy, &keep) {
+                Ok(output) => print!("{}", output),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        [command] if command == "repl" => repl::repl(strategy),
+        [source] if source != "dump" => {
+            event_router(notify::op::WRITE, source, strategy, &keep)
+        }
+        _ => {
+            eprintln!("{}", usage);
+            std::process::exit(1);
+        }
+    }
 
     std::process::exit(0);
 
@@ -53,62 +119,139 @@ fn main() {
     // }
 }
 
-fn event_router(operation: notify::Op, source: &String) {
-    match operation {
-        notify::op::WRITE => {
-            let msg = match compile(source) {
-                Ok(msg) => msg,
-                Err(msg) => msg,
-            };
-            let error_path = &mut source.clone();
-            error_path.push_str(&".errors".to_string());
-            write_file(error_path, &msg);
-        }
-        _ => {}
+fn event_router(
+    operation: notify::Op,
+    source: &String,
+    strategy: Strategy,
+    keep: &[String],
+) {
+    if operation == notify::op::WRITE {
+        let result = match source.ends_with(".rplir") {
+            true => run(source),
+            false => compile(source, strategy, keep),
+        };
+        let msg = match result {
+            Ok(msg) => msg,
+            Err(msg) => msg,
+        };
+        let error_path = &mut source.clone();
+        error_path.push_str(".errors");
+        write_file(error_path, &msg);
     };
 }
 
-fn compile(source: &String) -> Result<String, String> {
+fn compile(
+    source: &String,
+    strategy: Strategy,
+    keep: &[String],
+) -> Result<String, String> {
     let input = read_file(source);
     //
     //
     let tokens = tokenizer::tokenizer(&input)?;
     let token_path = &mut source.clone();
-    token_path.push_str(&".tokens".to_string());
+    token_path.push_str(".tokens");
     write_file(token_path, &token_string(&tokens));
     //
     //
     let parse = parser::parser(&tokens)?;
     let parse_path = &mut source.clone();
-    parse_path.push_str(&".ast".to_string());
+    parse_path.push_str(".ast");
     write_file(parse_path, &ast_string(&parse));
 
     //
     //
-    let (expand, maps) = expander::expander(&parse)?;
+    let spans = parser::spans(&tokens);
+    let (expand, maps, sizes) =
+        expander::expander(&parse, &spans, strategy)?;
     let expand_path = &mut source.clone();
-    expand_path.push_str(&".expand".to_string());
+    expand_path.push_str(".expand");
     write_file(expand_path, &format!("{:#?}", &expand));
 
+    let size_path = &mut source.clone();
+    size_path.push_str(".size");
+    write_file(size_path, &sizes.report());
+
     let maps_path = &mut source.clone();
-    maps_path.push_str(&".maps".to_string());
+    maps_path.push_str(".maps");
     write_file(maps_path, &format!("{:#?}", &maps));
 
     //
     //
-    // let (typed, map) = typer::typer(&expand, map)?;
-    // let typed_path = &mut source.clone();
-    // typed_path.push_str(&".typed".to_string());
-    // write_file(typed_path, &format!("{:#?}", &typed));
+    let (typed, warnings) = typer::typer(&expand, &maps)?;
+    let typed_path = &mut source.clone();
+    typed_path.push_str(".typed");
+    write_file(typed_path, &format!("{:#?}", &typed));
+
+    //
+    //
+    let mut program = reducer::reducer(&expand, &maps)?;
+    let dead = eliminator::eliminator(&mut program, keep)?;
+    let dead_path = &mut source.clone();
+    dead_path.push_str(".dead");
+    write_file(dead_path, &dead);
 
-    let _ = reducer::reducer(&expand, &maps)?;
-    // let typed_path = &mut source.clone();
-    // typed_path.push_str(&".typed".to_string());
-    // write_file(typed_path, &format!("{:#?}", &typed));
+    // the backends run what's written, not what was reduced
+    let ir_path = &mut source.clone();
+    ir_path.push_str(".rplir");
+    write_file(ir_path, &ir::print(&ir::ir(&program)));
+    let ir = ir::parse(&read_file(ir_path))?;
+    backends(source, &ir)?;
+
+    let mut msg = String::new();
+    for warning in warnings.iter().chain(&program.warnings) {
+        msg.push_str(warning);
+        msg.push('\n');
+    }
+    msg.push_str("no errors");
+    Ok(msg)
+}
+
+// the program as a graph, only dot so far
+fn dump(
+    source: &String,
+    format: &str,
+    strategy: Strategy,
+    keep: &[String],
+) -> Result<String, String> {
+    if format != "dot" {
+        return Err(format!(
+            "Error: --format {:?} isn't a format, dump --format dot",
+            format
+        ));
+    }
+    let input = read_file(source);
+    let tokens = tokenizer::tokenizer(&input)?;
+    let parse = parser::parser(&tokens)?;
+    let spans = parser::spans(&tokens);
+    let (expand, maps, _) = expander::expander(&parse, &spans, strategy)?;
+    // a program that doesn't reduce, like one with an error, is only its
+    // scopes
+    let ir = reducer::reducer(&expand, &maps).and_then(|mut program| {
+        eliminator::eliminator(&mut program, keep)?;
+        Ok(ir::ir(&program))
+    });
+    Ok(dot::dot(&maps, &spans, ir.as_ref().map_err(|e| e.clone())))
+}
 
+// a .rplir file is run as it is
+fn run(source: &String) -> Result<String, String> {
+    let ir = ir::parse(&read_file(source))?;
+    backends(source, &ir)?;
     Ok("no errors".to_string())
 }
 
+fn backends(source: &str, ir: &ir::Ir) -> Result<(), String> {
+    let run_path = &mut source.to_owned();
+    run_path.push_str(".run");
+    write_file(run_path, &runtime::runtime(ir)?);
+
+    let c_path = &mut source.to_owned();
+    c_path.push_str(".c");
+    write_file(c_path, &transpiler::transpiler(ir)?);
+    Ok(())
+}
+
 fn read_file(path: &String) -> String {
     match fs::read_to_string(path) {
         Ok(v) => v,
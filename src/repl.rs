@@ -0,0 +1,322 @@
+// rpl repl, a root graph that's edited a point at a time
+//
+// every point entered is a point of the root, x: 10 + 20, entering x again
+// replaces it. the root is compiled again after every edit, an edit that
+// doesn't compile is dropped, and the values of the points that changed
+// are shown, inputs keep the values they're set to across edits, what's
+// latched into registers starts over
+//
+// between edits the root runs in one runtime, :set and :fire only pull
+// the points that depend on what changed
+
+use crate::compiler::{
+    eliminator, expander, ir, parser, reducer, runtime, tokenizer, typer,
+};
+use expander::{Maps, Strategy};
+use parser::Ast;
+use reducer::Point;
+use runtime::Runtime;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "x: 10 + 20     define or replace a point of the root
+10 + 20        a point without a label is it
+:type x        the type of x, or of a point inside it, x.y
+:deps x        the points x needs
+:set x 5       write input x, like x: [i32], and fire it
+:fire w        fire clock w, like w: 1s, or input w
+:list          the points of the root
+:quit";
+
+// a root graph compiled
+struct Build {
+    maps: Maps,
+    typs: typer::Typs,
+    ir: ir::Ir,
+    warnings: Vec<String>,
+}
+
+pub(crate) struct Repl {
+    points: Vec<(String, String)>, // label and source, in the order entered
+    inputs: BTreeMap<String, Point>, // by label
+    values: BTreeMap<String, String>, // as last shown, by label
+    build: Option<Build>,
+    runtime: Option<Runtime>, // the build running
+    strategy: Strategy,
+}
+
+pub fn repl(strategy: Strategy) {
+    let mut repl = Repl::new(strategy);
+    println!("rpl repl, :help for help");
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        match repl.line(line.trim()) {
+            None => break,
+            Some(Ok(output)) if output.is_empty() => {}
+            Some(Ok(output)) => println!("{}", output),
+            Some(Err(e)) => println!("{}", e),
+        }
+    }
+}
+
+fn build(source: &str, strategy: Strategy) -> Result<Build, String> {
+    let tokens = tokenizer::tokenizer(source)?;
+    let parse = parser::parser(&tokens)?;
+    let spans = parser::spans(&tokens);
+    let (expand, maps, _) = expander::expander(&parse, &spans, strategy)?;
+    let (typs, mut warnings) = typer::typer(&expand, &maps)?;
+    let mut program = reducer::reducer(&expand, &maps)?;
+    eliminator::eliminator(&mut program, &[])?;
+    warnings.extend(program.warnings.iter().cloned());
+    let ir = ir::ir(&program);
+    Ok(Build {
+        maps,
+        typs,
+        ir,
+        warnings,
+    })
+}
+
+impl Repl {
+    pub(crate) fn new(strategy: Strategy) -> Self {
+        Repl {
+            points: Vec::new(),
+            inputs: BTreeMap::new(),
+            values: BTreeMap::new(),
+            build: None,
+            runtime: None,
+            strategy,
+        }
+    }
+
+    // a line entered, what's printed back, none once it quits
+    pub(crate) fn line(
+        &mut self,
+        line: &str,
+    ) -> Option<Result<String, String>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        Some(match words[..] {
+            [] => Ok(String::new()),
+            [":quit" | ":q"] => return None,
+            [":help"] => Ok(HELP.to_string()),
+            [":list"] => Ok(self
+                .points
+                .iter()
+                .map(|(_, source)| source.as_str())
+                .collect::<Vec<&str>>()
+                .join("\n")),
+            [":type", path] => self.typ(path),
+            [":deps", path] => self.deps(path),
+            [":set", label, value] => self.set(label, value),
+            [":fire", label] => self.fire(label),
+            [command, ..] if command.starts_with(':') => Err(format!(
+                "Error: {} isn't a command, :help lists them",
+                command
+            )),
+            _ => self.edit(line),
+        })
+    }
+
+    // x: 10 + 20 defines x, or replaces it
+    fn edit(&mut self, line: &str) -> Result<String, String> {
+        let tokens = tokenizer::tokenizer(line)?;
+        let (label, source) = match parser::parser(&tokens)? {
+            Ast::Graph(_, points) => match &points[..] {
+                [Ast::Key(label, _)] if label.starts_with('<') => {
+                    return Err(
+                        "Error: a return is only a point of a graph, like m: { a: 1 -> a }"
+                            .to_string(),
+                    )
+                }
+                [Ast::Key(label, _)] => (label.clone(), line.to_string()),
+                [_] => ("it".to_string(), format!("it: {}", line)),
+                _ => {
+                    return Err(
+                        "Error: one point at a time, like x: 10 + 20"
+                            .to_string(),
+                    )
+                }
+            },
+            x => return Err(format!("Error: {:?} is not a point", x)),
+        };
+
+        let mut points = self.points.clone();
+        match points.iter_mut().find(|(l, _)| *l == label) {
+            Some(point) => point.1 = source,
+            None => points.push((label.clone(), source)),
+        }
+        let text: Vec<&str> =
+            points.iter().map(|(_, source)| source.as_str()).collect();
+        let build = build(&text.join("\n"), self.strategy)?;
+        let mut runtime = Runtime::new(&build.ir);
+        for (input, value) in &self.inputs {
+            if let Some(idx) =
+                build.ir.labels.get(&format!("root.{}", input))
+            {
+                runtime.write(*idx, value.clone()).ok();
+            }
+        }
+        self.points = points;
+        self.build = Some(build);
+        self.runtime = Some(runtime);
+
+        let mut output = self.warnings();
+        output.extend(self.refresh(&label)?);
+        let typ = self.typ(&label)?;
+        if let Some(first) = output
+            .iter_mut()
+            .find(|line| line.starts_with(&format!("{}: ", label)))
+        {
+            first.push_str(&format!(" ({})", &typ[label.len() + 2..]));
+        }
+        Ok(output.join("\n"))
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        match &self.build {
+            Some(build) => build.warnings.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    // the values of the points of the root, label first, then every other
+    // point whose value changed
+    fn refresh(&mut self, label: &str) -> Result<Vec<String>, String> {
+        let (build, runtime) = match (&self.build, &mut self.runtime) {
+            (Some(build), Some(runtime)) => (build, runtime),
+            _ => return Err("Error: no points yet".to_string()),
+        };
+        let mut values = BTreeMap::new();
+        for (path, idx) in &build.ir.labels {
+            let name = match path.strip_prefix("root.") {
+                Some(name) if !name.contains('.') => name,
+                _ => continue,
+            };
+            if expander::is_ordinal(name) {
+                continue;
+            }
+            let value = match runtime.show(*idx) {
+                Ok(value) if runtime.overflows.contains(idx) => {
+                    format!("{} @overflow", value)
+                }
+                Ok(value) => value,
+                Err(e) => e,
+            };
+            values.insert(name.to_string(), value);
+        }
+        let mut output = Vec::new();
+        if let Some(value) = values.get(label) {
+            output.push(format!("{}: {}", label, value));
+        }
+        for (name, value) in &values {
+            if name != label && self.values.get(name) != Some(value) {
+                output.push(format!("{}: {}", name, value));
+            }
+        }
+        self.values = values;
+        Ok(output)
+    }
+
+    // x or x.y, the type the typer gave it
+    fn typ(&self, path: &str) -> Result<String, String> {
+        let build = self.build.as_ref().ok_or("Error: no points yet")?;
+        let full = format!("root.{}", path);
+        let (graph, label) =
+            full.rsplit_once('.').unwrap_or(("root", path));
+        let typ = build
+            .maps
+            .paths
+            .get(graph)
+            .and_then(|eid| build.typs[*eid].get(label));
+        match typ {
+            Some(typ) => Ok(format!("{}: {:?}", path, typ)),
+            None => Err(format!("Error: {} is not a point", path)),
+        }
+    }
+
+    fn deps(&self, path: &str) -> Result<String, String> {
+        let build = self.build.as_ref().ok_or("Error: no points yet")?;
+        let full = format!("root.{}", path);
+        let symbol = build
+            .maps
+            .at(&full)
+            .ok_or_else(|| format!("Error: {} is not a point", path))?;
+        let mut deps = Vec::new();
+        build.maps.deps(&symbol.point, true, &mut deps);
+        deps.sort();
+        deps.dedup();
+        let deps: Vec<&str> = deps
+            .iter()
+            .map(|dep| dep.strip_prefix("root.").unwrap_or(dep))
+            .collect();
+        match deps.is_empty() {
+            true => Ok(format!("{} needs no other points", path)),
+            false => Ok(format!("{} needs {}", path, deps.join(", "))),
+        }
+    }
+
+    // :set x 5, an input keeps its value until it's set again
+    fn set(&mut self, label: &str, value: &str) -> Result<String, String> {
+        let build = self.build.as_ref().ok_or("Error: no points yet")?;
+        let idx = match build.ir.labels.get(&format!("root.{}", label)) {
+            Some(idx) if matches!(build.ir.code[*idx], Point::Input(_)) => {
+                *idx
+            }
+            Some(_) => {
+                return Err(format!(
+                    "Error: {} isn't an input, inputs are points like {}: [i32]",
+                    label, label
+                ))
+            }
+            None => return Err(format!("Error: {} is not a point", label)),
+        };
+        let value = match (value.parse::<i64>(), value.parse::<f64>()) {
+            (Ok(v), _) => Point::Integer(v),
+            (_, Ok(v)) => Point::Decimal(v),
+            _ => {
+                return Err(format!(
+                    "Error: {} is not an Integer or a Decimal",
+                    value
+                ))
+            }
+        };
+        if let Some(runtime) = &mut self.runtime {
+            runtime.set(idx, value.clone())?;
+        }
+        self.inputs.insert(label.to_string(), value);
+        Ok(self.refresh(label)?.join("\n"))
+    }
+
+    // :fire w, a clock flips, and the latches it drives take their values
+    fn fire(&mut self, label: &str) -> Result<String, String> {
+        let build = self.build.as_ref().ok_or("Error: no points yet")?;
+        let idx = match build.ir.labels.get(&format!("root.{}", label)) {
+            Some(idx)
+                if matches!(
+                    build.ir.code[*idx],
+                    Point::Clock(_) | Point::Input(_)
+                ) =>
+            {
+                *idx
+            }
+            Some(_) => {
+                return Err(format!(
+                    "Error: {} isn't a clock or an input, clocks are points like {}: 1s",
+                    label, label
+                ))
+            }
+            None => return Err(format!("Error: {} is not a point", label)),
+        };
+        if let Some(runtime) = &mut self.runtime {
+            runtime.fire(idx)?;
+        }
+        Ok(self.refresh(label)?.join("\n"))
+    }
+}